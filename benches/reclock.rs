@@ -0,0 +1,142 @@
+//! Benchmarks the throughput and memory use of the reclock pipelines.
+//!
+//! Every configuration reclocks a number of records into frontiers of a given width, in a grid
+//! lattice of a given depth, using a number of workers. It prints one line with the records that
+//! were reclocked per second and the peak number of updates held in arrangements, summed over the
+//! workers. The columns are fixed so that the output of different runs can be compared directly.
+
+use std::time::{Duration, Instant};
+
+use differential_dataflow::input::Input;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::Probe;
+
+use demo_reclock_reduce::finite::{FiniteLattice, FiniteTime};
+use demo_reclock_reduce::harness::{self, Pipeline};
+use demo_reclock_reduce::{reclock_direct, reclock_frontiers};
+
+const RECORDS: [usize; 2] = [1_000, 10_000];
+const WIDTHS: [usize; 3] = [1, 2, 4];
+const DEPTHS: [usize; 2] = [4, 8];
+const WORKERS: [usize; 2] = [1, 2];
+
+/// A source update and its frontier, as fed to the reclock pipelines.
+type Record = (((u64, u64), Vec<FiniteTime>), i64);
+
+/// The lattice of pairs `(x, y)` with both coordinates below `depth`, ordered coordinate-wise.
+fn grid(depth: usize) -> FiniteLattice {
+    let name = |x: usize, y: usize| format!("{x}.{y}");
+    let mut elements = vec![];
+    let mut covers = vec![];
+    for x in 0..depth {
+        for y in 0..depth {
+            elements.push(name(x, y));
+            if x + 1 < depth {
+                covers.push((name(x, y), name(x + 1, y)));
+            }
+            if y + 1 < depth {
+                covers.push((name(x, y), name(x, y + 1)));
+            }
+        }
+    }
+    let elements: Vec<&str> = elements.iter().map(String::as_str).collect();
+    let covers: Vec<(&str, &str)> = covers
+        .iter()
+        .map(|(lower, upper)| (lower.as_str(), upper.as_str()))
+        .collect();
+    FiniteLattice::from_hasse(&elements, &covers).expect("grid is a lattice")
+}
+
+/// Generates `count` records, each reclocked into an antichain of `width` elements of the grid at
+/// a pseudo random position.
+fn records(count: usize, width: usize, lattice: &FiniteLattice, depth: usize) -> Vec<Record> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % bound
+    };
+    (0..count)
+        .map(|i| {
+            let (x, y) = (next(depth - width + 1), next(depth - width + 1));
+            let mut frontier: Vec<_> = (0..width)
+                .map(|j| {
+                    let name = format!("{}.{}", x + j, y + width - 1 - j);
+                    lattice.element(&name).expect("element of the grid")
+                })
+                .collect();
+            frontier.sort();
+            (((i as u64, i as u64), frontier), 1)
+        })
+        .collect()
+}
+
+/// Reclocks `records` and returns the elapsed time and the peak arrangement size.
+fn measure(pipeline: Pipeline, workers: usize, records: Vec<Record>) -> (Duration, usize) {
+    let start = Instant::now();
+    let guards = timely::execute(timely::Config::process(workers), move |worker| {
+        let size = harness::track_arrangements(worker);
+
+        let mut probe = Handle::new();
+        let mut input = worker.dataflow::<FiniteTime, _, _>(|scope| {
+            let (input, source) = scope.new_collection();
+            let reclocked = match pipeline {
+                Pipeline::Reduce => reclock_frontiers(&source),
+                Pipeline::Direct => reclock_direct(&source),
+            };
+            reclocked.probe_with(&mut probe);
+            input
+        });
+
+        let (index, peers) = (worker.index(), worker.peers());
+        for (i, (update, diff)) in records.iter().enumerate() {
+            if i % peers == index {
+                input.update(update.clone(), *diff);
+            }
+        }
+        input.close();
+        while !probe.done() {
+            worker.step();
+        }
+        worker.log_register().flush();
+        size.get().peak as usize
+    })
+    .expect("failed to execute dataflow");
+
+    let peaks = guards
+        .join()
+        .into_iter()
+        .map(|peak| peak.expect("worker failed"));
+    let peak = peaks.sum();
+    (start.elapsed(), peak)
+}
+
+fn main() {
+    println!(
+        "{:<8} {:>8} {:>6} {:>6} {:>8} {:>12} {:>14}",
+        "pipeline", "records", "width", "depth", "workers", "records/s", "peak arranged"
+    );
+    for depth in DEPTHS {
+        let lattice = grid(depth);
+        for width in WIDTHS.into_iter().filter(|width| *width <= depth) {
+            for count in RECORDS {
+                let records = records(count, width, &lattice, depth);
+                for workers in WORKERS {
+                    for pipeline in [Pipeline::Reduce, Pipeline::Direct] {
+                        let (elapsed, peak) = measure(pipeline, workers, records.clone());
+                        let rate = count as f64 / elapsed.as_secs_f64();
+                        let pipeline = match pipeline {
+                            Pipeline::Reduce => "reduce",
+                            Pipeline::Direct => "direct",
+                        };
+                        println!(
+                            "{pipeline:<8} {count:>8} {width:>6} {depth:>6} {workers:>8} \
+                             {rate:>12.0} {peak:>14}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
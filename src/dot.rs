@@ -0,0 +1,49 @@
+//! Renders finite lattices as Hasse diagrams in the DOT language of Graphviz.
+//!
+//! Elements are drawn from bottom to top, with an edge from every element to the elements that
+//! cover it. Nodes can be annotated with extra lines, such as the multiplicities of reclocked
+//! records, and the elements of a frontier are highlighted.
+
+use std::fmt::{Debug, Write};
+
+use timely::order::PartialOrder;
+
+/// Renders the Hasse diagram of the partial order of `elements`, highlighting the elements of
+/// `frontier` and labelling every node with its element followed by its `annotations`.
+pub fn hasse_diagram<T, F>(elements: &[T], frontier: &[T], mut annotations: F) -> String
+where
+    T: PartialOrder + Debug,
+    F: FnMut(&T) -> Vec<String>,
+{
+    let below = |a: &T, b: &T| a.less_equal(b) && !b.less_equal(a);
+    let covers = |a: &T, b: &T| below(a, b) && !elements.iter().any(|c| below(a, c) && below(c, b));
+
+    let mut dot = String::new();
+    writeln!(dot, "digraph lattice {{").unwrap();
+    writeln!(dot, "    rankdir=BT;").unwrap();
+    writeln!(dot, "    node [shape=box];").unwrap();
+    for (i, element) in elements.iter().enumerate() {
+        let mut lines = vec![format!("{element:?}")];
+        lines.extend(annotations(element));
+        let label: Vec<String> = lines.iter().map(|line| escape(line)).collect();
+        let mut attributes = format!("label=\"{}\"", label.join("\\n"));
+        if frontier.contains(element) {
+            attributes.push_str(", style=filled, fillcolor=\"#ffe08a\"");
+        }
+        writeln!(dot, "    n{i} [{attributes}];").unwrap();
+    }
+    for (i, a) in elements.iter().enumerate() {
+        for (j, b) in elements.iter().enumerate() {
+            if covers(a, b) {
+                writeln!(dot, "    n{i} -> n{j};").unwrap();
+            }
+        }
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+/// Escapes `text` for a double quoted DOT string, in which only quotes and backslashes are special.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,149 @@
+//! Explains how a record is reclocked, step by step.
+//!
+//! The reclock dataflow expands a record into `AltNeu` updates with [`reclock_record`], reduces
+//! them so that the record has its original diff wherever it has copies, and keeps only the `Alt`
+//! updates of the reduce. The output of the reduce can only change at the joins of the times of its
+//! input, so all three steps can be computed for a single record without running the dataflow.
+
+use std::fmt;
+
+use differential_dataflow::difference::Abelian;
+use differential_dataflow::lattice::Lattice;
+use dogsdogsdogs::altneu::AltNeu;
+use timely::order::PartialOrder;
+use timely::progress::{Antichain, Timestamp};
+
+use crate::reclock::{join_closure, reclock_record};
+
+/// The updates of every step of reclocking a record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation<T, R> {
+    /// The updates generated by [`reclock_record`]
+    pub expanded: Vec<(AltNeu<T>, R)>,
+    /// The updates emitted by the reduce
+    pub reduced: Vec<(AltNeu<T>, R)>,
+    /// The accumulated multiplicity of the record in the reclocked collection at every time
+    pub multiplicities: Vec<(T, R)>,
+}
+
+/// Explains how a record with `diff` is reclocked into `frontier`, reporting its multiplicity at
+/// each of `elements`.
+pub fn explain<D, FromTime, T, R>(
+    record: (D, FromTime, R),
+    frontier: Antichain<T>,
+    elements: &[T],
+) -> Explanation<T, R>
+where
+    D: timely::Data,
+    FromTime: timely::Data,
+    T: Timestamp + Lattice,
+    R: Abelian,
+{
+    let diff = record.2.clone();
+    let expanded: Vec<_> = reclock_record(record, frontier)
+        .into_iter()
+        .filter(|(_, _, diff)| !diff.is_zero())
+        .map(|(_, time, diff)| (time, diff))
+        .collect();
+
+    // The reduce asserts the original diff wherever the record has copies and nothing elsewhere
+    let times: Vec<_> = expanded.iter().map(|(time, _)| time.clone()).collect();
+    let mut reduced: Vec<(AltNeu<T>, R)> = vec![];
+    for time in join_closure(&times) {
+        let input = accumulate(&expanded, &time);
+        let mut output = if input.is_zero() {
+            R::zero()
+        } else {
+            diff.clone()
+        };
+        output.plus_equals(&accumulate(&reduced, &time).negate());
+        if !output.is_zero() {
+            reduced.push((time, output));
+        }
+    }
+
+    let integrated: Vec<_> = reduced
+        .iter()
+        .filter(|(time, _)| !time.neu)
+        .map(|(time, diff)| (time.time.clone(), diff.clone()))
+        .collect();
+    let multiplicities = elements
+        .iter()
+        .map(|element| (element.clone(), accumulate(&integrated, element)))
+        .collect();
+
+    Explanation {
+        expanded,
+        reduced,
+        multiplicities,
+    }
+}
+
+/// Accumulates the updates at `time`.
+fn accumulate<T: PartialOrder, R: Abelian>(updates: &[(T, R)], time: &T) -> R {
+    let mut accum = R::zero();
+    for (t, diff) in updates {
+        if t.less_equal(time) {
+            accum.plus_equals(diff);
+        }
+    }
+    accum
+}
+
+impl<T: fmt::Debug + Ord + Clone, R: fmt::Debug> fmt::Display for Explanation<T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |time: &AltNeu<T>| {
+            let side = if time.neu { "Neu" } else { "Alt" };
+            format!("{side}({:?})", time.time)
+        };
+        let diff = |updates: &[(AltNeu<T>, R)], time: &AltNeu<T>| {
+            let update = updates.iter().find(|(t, _)| t == time);
+            update.map_or(String::new(), |(_, diff)| format!("{diff:?}"))
+        };
+
+        let mut times: Vec<_> = self
+            .expanded
+            .iter()
+            .chain(&self.reduced)
+            .map(|(t, _)| t)
+            .collect();
+        times.sort();
+        times.dedup();
+        let mut rows = vec![[
+            "time".to_owned(),
+            "reclock_record".to_owned(),
+            "reduce".to_owned(),
+        ]];
+        for time in times {
+            rows.push([
+                side(time),
+                diff(&self.expanded, time),
+                diff(&self.reduced, time),
+            ]);
+        }
+        table(f, &rows)?;
+
+        writeln!(f)?;
+        let mut rows = vec![["element".to_owned(), "multiplicity".to_owned()]];
+        for (element, diff) in &self.multiplicities {
+            rows.push([format!("{element:?}"), format!("{diff:?}")]);
+        }
+        table(f, &rows)
+    }
+}
+
+/// Writes `rows` as an indented table with left aligned columns.
+fn table<const N: usize>(f: &mut fmt::Formatter<'_>, rows: &[[String; N]]) -> fmt::Result {
+    let widths: Vec<usize> = (0..N)
+        .map(|i| rows.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let cells: Vec<_> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        writeln!(f, "    {}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,351 @@
+//! Defines a finite lattice time whose shape is supplied at runtime as a Hasse diagram.
+//!
+//! The partial order, join and meet of every pair of elements are derived from the transitive
+//! closure of the covering relation when the lattice is built, so any finite lattice can be used
+//! as a timestamp without writing out its order by hand. For example the lattice of
+//! [`crate::order::Time`] is built with:
+//!
+//! ```
+//! use demo_reclock_reduce::finite::FiniteLattice;
+//!
+//! let lattice = FiniteLattice::from_hasse(
+//!     &["A", "B", "C", "D", "E", "F", "G"],
+//!     &[
+//!         ("A", "B"),
+//!         ("A", "C"),
+//!         ("A", "D"),
+//!         ("B", "E"),
+//!         ("C", "E"),
+//!         ("C", "F"),
+//!         ("D", "F"),
+//!         ("E", "G"),
+//!         ("F", "G"),
+//!     ],
+//! )
+//! .unwrap();
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, Weak};
+
+use differential_dataflow::lattice::Lattice;
+use serde::{Deserialize, Serialize};
+use timely::order::PartialOrder;
+use timely::progress::timestamp::{PathSummary, Refines, Timestamp};
+
+/// Every lattice of this process that is still in use, by id. The registry does not keep lattices
+/// alive, so a lattice is forgotten once its last [`FiniteLattice`] and elements are dropped.
+static LATTICES: Mutex<BTreeMap<u64, Weak<Tables>>> = Mutex::new(BTreeMap::new());
+
+/// The order, join and meet of every pair of elements of a finite lattice, indexed by element.
+#[derive(Debug)]
+struct Tables {
+    /// A hash of the names and order of the elements, which identifies the lattice across
+    /// processes
+    id: u64,
+    names: Vec<String>,
+    less_equal: Vec<Vec<bool>>,
+    join: Vec<Vec<usize>>,
+    meet: Vec<Vec<usize>>,
+}
+
+/// A finite lattice built from its elements and covering relation.
+///
+/// Elements are numbered in a linear extension of the partial order, so the bottom element is
+/// always numbered zero and the total order of [`FiniteTime`] is consistent with the lattice.
+#[derive(Clone, Debug)]
+pub struct FiniteLattice {
+    tables: Arc<Tables>,
+}
+
+impl FiniteLattice {
+    /// Builds a lattice from the names of its elements and the `(lower, upper)` pairs of its
+    /// covering relation.
+    ///
+    /// Fails if the covering relation mentions unknown elements or has a cycle, or if some pair of
+    /// elements lacks a unique least upper bound or greatest lower bound.
+    pub fn from_hasse(elements: &[&str], covers: &[(&str, &str)]) -> Result<Self, String> {
+        let n = elements.len();
+        if n == 0 {
+            return Err("a lattice must have at least one element".to_owned());
+        }
+        for (i, name) in elements.iter().enumerate() {
+            if elements[..i].contains(name) {
+                return Err(format!("duplicate element {name}"));
+            }
+        }
+
+        let position = |name: &str| {
+            elements
+                .iter()
+                .position(|e| *e == name)
+                .ok_or_else(|| format!("unknown element {name}"))
+        };
+        let mut covered_by = vec![vec![]; n];
+        for (lower, upper) in covers {
+            covered_by[position(lower)?].push(position(upper)?);
+        }
+
+        // The transitive closure of the covering relation is the partial order
+        let mut less_equal = vec![vec![false; n]; n];
+        for (a, row) in less_equal.iter_mut().enumerate() {
+            let mut stack = vec![a];
+            while let Some(b) = stack.pop() {
+                if !row[b] {
+                    row[b] = true;
+                    stack.extend(&covered_by[b]);
+                }
+            }
+        }
+        for a in 0..n {
+            for b in 0..a {
+                if less_equal[a][b] && less_equal[b][a] {
+                    return Err(format!("{} and {} form a cycle", elements[a], elements[b]));
+                }
+            }
+        }
+
+        // An element is strictly above another only if it has strictly more elements below it, so
+        // sorting by the size of the down set yields a linear extension.
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by_key(|&a| (0..n).filter(|&b| less_equal[b][a]).count());
+        let less_equal: Vec<Vec<bool>> = order
+            .iter()
+            .map(|&a| order.iter().map(|&b| less_equal[a][b]).collect())
+            .collect();
+        let names: Vec<String> = order.iter().map(|&a| elements[a].to_owned()).collect();
+
+        // The least element among the upper bounds of `a` and `b`, or the greatest among the lower
+        // bounds, depending on how `le` is oriented.
+        let bound = |a: usize, b: usize, le: &dyn Fn(usize, usize) -> bool| {
+            let bounds: Vec<usize> = (0..n).filter(|&c| le(a, c) && le(b, c)).collect();
+            bounds
+                .iter()
+                .copied()
+                .find(|&c| bounds.iter().all(|&d| le(c, d)))
+        };
+        let upper = |a: usize, b: usize| less_equal[a][b];
+        let lower = |a: usize, b: usize| less_equal[b][a];
+
+        let mut join = vec![vec![0; n]; n];
+        let mut meet = vec![vec![0; n]; n];
+        for a in 0..n {
+            for b in 0..n {
+                join[a][b] = bound(a, b, &upper)
+                    .ok_or_else(|| format!("{} and {} have no join", names[a], names[b]))?;
+                meet[a][b] = bound(a, b, &lower)
+                    .ok_or_else(|| format!("{} and {} have no meet", names[a], names[b]))?;
+            }
+        }
+
+        let mut hasher = DefaultHasher::new();
+        (&names, &less_equal).hash(&mut hasher);
+        let id = hasher.finish();
+
+        // Lattices are registered so that their elements can be deserialized by id, and building
+        // the same lattice twice shares the tables of the first one
+        let mut lattices = LATTICES.lock().expect("lattice registry poisoned");
+        lattices.retain(|_id, tables| tables.strong_count() > 0);
+        let tables = match lattices.get(&id).and_then(Weak::upgrade) {
+            Some(tables) if tables.names == names && tables.less_equal == less_equal => tables,
+            Some(_) => return Err(format!("lattice id {id:016x} is already taken")),
+            None => {
+                let tables = Arc::new(Tables {
+                    id,
+                    names,
+                    less_equal,
+                    join,
+                    meet,
+                });
+                lattices.insert(id, Arc::downgrade(&tables));
+                tables
+            }
+        };
+        Ok(Self { tables })
+    }
+
+    /// Returns the element with the given name.
+    pub fn element(&self, name: &str) -> Option<FiniteTime> {
+        let index = self.tables.names.iter().position(|n| n == name)?;
+        Some(self.time(index))
+    }
+
+    /// Returns all the elements of the lattice, in a linear extension of its partial order.
+    pub fn elements(&self) -> Vec<FiniteTime> {
+        (0..self.tables.names.len()).map(|i| self.time(i)).collect()
+    }
+
+    fn time(&self, index: usize) -> FiniteTime {
+        FiniteTime {
+            index,
+            lattice: Some(Arc::clone(&self.tables)),
+        }
+    }
+}
+
+/// An element of a [`FiniteLattice`].
+///
+/// Every element carries the lattice it belongs to, except for the one returned by
+/// `Timestamp::minimum` which has no way of knowing it and compares as the bottom element of any
+/// lattice. Elements are compared by their position in the lattice only, so elements of different
+/// lattices must not be mixed in the same dataflow.
+///
+/// An element is serialized as its position and the id of its lattice, and can only be
+/// deserialized in a process that holds on to the same lattice.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "Encoded", try_from = "Encoded")]
+pub struct FiniteTime {
+    index: usize,
+    lattice: Option<Arc<Tables>>,
+}
+
+/// The serialized form of a [`FiniteTime`].
+#[derive(Serialize, Deserialize)]
+struct Encoded {
+    index: usize,
+    lattice: Option<u64>,
+}
+
+impl From<FiniteTime> for Encoded {
+    fn from(time: FiniteTime) -> Self {
+        Self {
+            index: time.index,
+            lattice: time.lattice.map(|tables| tables.id),
+        }
+    }
+}
+
+impl TryFrom<Encoded> for FiniteTime {
+    type Error = String;
+
+    fn try_from(Encoded { index, lattice }: Encoded) -> Result<Self, String> {
+        let lattice = match lattice {
+            Some(id) => {
+                let lattices = LATTICES.lock().expect("lattice registry poisoned");
+                let tables = lattices
+                    .get(&id)
+                    .and_then(Weak::upgrade)
+                    .ok_or_else(|| format!("unknown lattice {id:016x}"))?;
+                if index >= tables.names.len() {
+                    return Err(format!("lattice {id:016x} has no element {index}"));
+                }
+                Some(tables)
+            }
+            None if index == 0 => None,
+            None => return Err(format!("element {index} has no lattice")),
+        };
+        Ok(Self { index, lattice })
+    }
+}
+
+impl FiniteTime {
+    /// Returns the name of this element, if it is known.
+    pub fn name(&self) -> Option<&str> {
+        let tables = self.lattice.as_ref()?;
+        Some(&tables.names[self.index])
+    }
+
+    /// Returns the lattice tables of either of the two elements. Only the bottom element can lack
+    /// them, so if neither has them both are the bottom element.
+    fn tables<'a>(&'a self, other: &'a Self) -> Option<&'a Arc<Tables>> {
+        self.lattice.as_ref().or(other.lattice.as_ref())
+    }
+}
+
+impl fmt::Debug for FiniteTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "#{}", self.index),
+        }
+    }
+}
+
+impl PartialEq for FiniteTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl Eq for FiniteTime {}
+
+impl PartialOrd for FiniteTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FiniteTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl Hash for FiniteTime {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl Timestamp for FiniteTime {
+    type Summary = ();
+
+    fn minimum() -> Self {
+        Self {
+            index: 0,
+            lattice: None,
+        }
+    }
+}
+
+impl PathSummary<FiniteTime> for () {
+    fn results_in(&self, src: &FiniteTime) -> Option<FiniteTime> {
+        Some(src.clone())
+    }
+    fn followed_by(&self, _other: &Self) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Refines<()> for FiniteTime {
+    fn to_inner(_other: ()) -> Self {
+        Self::minimum()
+    }
+    fn to_outer(self) {}
+    fn summarize(_path: Self::Summary) -> <() as Timestamp>::Summary {}
+}
+
+impl PartialOrder for FiniteTime {
+    fn less_equal(&self, other: &Self) -> bool {
+        match self.tables(other) {
+            Some(tables) => tables.less_equal[self.index][other.index],
+            None => true,
+        }
+    }
+}
+
+impl Lattice for FiniteTime {
+    fn join(&self, other: &Self) -> Self {
+        match self.tables(other) {
+            Some(tables) => Self {
+                index: tables.join[self.index][other.index],
+                lattice: Some(Arc::clone(tables)),
+            },
+            None => Self::minimum(),
+        }
+    }
+
+    fn meet(&self, other: &Self) -> Self {
+        match self.tables(other) {
+            Some(tables) => Self {
+                index: tables.meet[self.index][other.index],
+                lattice: Some(Arc::clone(tables)),
+            },
+            None => Self::minimum(),
+        }
+    }
+}
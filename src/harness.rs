@@ -0,0 +1,190 @@
+//! Runs the reclock dataflow in process and captures its output instead of printing it.
+//!
+//! The harness is meant for tests and tools that need to assert on the exact output of the
+//! dataflow. Records are introduced in batches, each at its own time, and spread over the workers.
+//! Once all of them have been reclocked, the updates that every worker observed are consolidated
+//! and grouped by the `IntoTime` they happened at.
+
+use std::cell::{Cell, RefCell};
+use std::hash::Hash;
+use std::rc::Rc;
+
+use differential_dataflow::consolidation::consolidate;
+use differential_dataflow::difference::{Abelian, Semigroup};
+use differential_dataflow::input::Input;
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::logging::DifferentialEvent;
+use differential_dataflow::ExchangeData;
+use timely::communication::Allocate;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::{Inspect, Probe};
+use timely::progress::Timestamp;
+use timely::worker::Worker;
+
+use crate::oracle::ReclockedRecord;
+use crate::reclock::{reclock_direct, reclock_frontiers};
+
+/// The implementation of reclocking that the harness runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pipeline {
+    /// [`reclock_frontiers`], which reduces the updates in an `AltNeu` scope
+    Reduce,
+    /// [`reclock_direct`], which emits the corrections of every update directly
+    Direct,
+}
+
+/// The consolidated updates of a reclocked collection, grouped by time and sorted.
+pub type Updates<D, FromTime, T, R> = Vec<(T, Vec<((D, FromTime), R)>)>;
+
+/// Reclocks `records` using `workers` worker threads and returns the reclocked updates.
+pub fn reclock<D, FromTime, T, R>(
+    workers: usize,
+    records: Vec<ReclockedRecord<D, FromTime, R, T>>,
+) -> Result<Updates<D, FromTime, T, R>, String>
+where
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    T: Timestamp + Lattice,
+    R: Abelian + ExchangeData + Hash,
+{
+    reclock_batches(workers, vec![(T::minimum(), records)])
+}
+
+/// Reclocks every batch of records at its time using `workers` worker threads and returns the
+/// reclocked updates.
+///
+/// The times of the batches must be increasing, and the records of a batch are visible no earlier
+/// than its time.
+pub fn reclock_batches<D, FromTime, T, R>(
+    workers: usize,
+    batches: Vec<(T, Vec<ReclockedRecord<D, FromTime, R, T>>)>,
+) -> Result<Updates<D, FromTime, T, R>, String>
+where
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    T: Timestamp + Lattice,
+    R: Abelian + ExchangeData + Hash,
+{
+    reclock_with(Pipeline::Reduce, workers, batches)
+}
+
+/// Reclocks every batch of records like [`reclock_batches`], using the given `pipeline`.
+pub fn reclock_with<D, FromTime, T, R>(
+    pipeline: Pipeline,
+    workers: usize,
+    batches: Vec<(T, Vec<ReclockedRecord<D, FromTime, R, T>>)>,
+) -> Result<Updates<D, FromTime, T, R>, String>
+where
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    T: Timestamp + Lattice,
+    R: Abelian + ExchangeData + Hash,
+{
+    let guards = timely::execute(timely::Config::process(workers), move |worker| {
+        let mut probe = Handle::new();
+        let output = Rc::new(RefCell::new(Vec::new()));
+
+        let mut input = worker.dataflow::<T, _, _>(|scope| {
+            let (input, source) = scope.new_collection::<((D, FromTime), Vec<T>), R>();
+            let output = Rc::clone(&output);
+            let reclocked = match pipeline {
+                Pipeline::Reduce => reclock_frontiers(&source),
+                Pipeline::Direct => reclock_direct(&source),
+            };
+            reclocked
+                .inner
+                .inspect(move |(update, time, diff)| {
+                    let time = time.clone();
+                    output
+                        .borrow_mut()
+                        .push(((time, update.clone()), diff.clone()))
+                })
+                .probe_with(&mut probe);
+            input
+        });
+
+        let (index, peers) = (worker.index(), worker.peers());
+        for (time, records) in batches.iter() {
+            input.advance_to(time.clone());
+            for (i, ((data, from_ts, diff), frontier)) in records.iter().enumerate() {
+                if i % peers == index {
+                    let mut frontier = frontier.elements().to_vec();
+                    frontier.sort();
+                    let update = ((data.clone(), from_ts.clone()), frontier);
+                    input.update(update, diff.clone());
+                }
+            }
+        }
+        input.close();
+        while !probe.done() {
+            worker.step();
+        }
+        output.take()
+    })?;
+
+    let mut updates = vec![];
+    for result in guards.join() {
+        updates.extend(result?);
+    }
+    Ok(group_by_time(updates))
+}
+
+/// Consolidates `updates` and groups them by their time.
+pub(crate) fn group_by_time<D, FromTime, T, R>(
+    mut updates: Vec<((T, (D, FromTime)), R)>,
+) -> Updates<D, FromTime, T, R>
+where
+    D: Ord,
+    FromTime: Ord,
+    T: Ord,
+    R: Semigroup,
+{
+    consolidate(&mut updates);
+
+    let mut grouped: Updates<D, FromTime, T, R> = vec![];
+    for ((time, update), diff) in updates {
+        match grouped.last_mut() {
+            Some((last, group)) if *last == time => group.push((update, diff)),
+            _ => grouped.push((time, vec![(update, diff)])),
+        }
+    }
+    grouped
+}
+
+/// The number of updates held in the arrangements of a worker.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArrangementSize {
+    pub current: isize,
+    pub peak: isize,
+}
+
+/// Tracks the number of updates held in the arrangements of the dataflows that `worker` builds
+/// from now on, as reported by the logging of differential.
+///
+/// Logged events are delivered in batches, so the size only accounts for the events that have
+/// been flushed with `worker.log_register().flush()` by the time it is read.
+pub fn track_arrangements<A: Allocate>(worker: &mut Worker<A>) -> Rc<Cell<ArrangementSize>> {
+    let size = Rc::new(Cell::new(ArrangementSize::default()));
+    let logged = Rc::clone(&size);
+    worker.log_register().insert::<DifferentialEvent, _>(
+        "differential/arrange",
+        move |_time, data| {
+            for (_, _, event) in data.drain(..) {
+                let delta = match event {
+                    DifferentialEvent::Batch(event) => event.length as isize,
+                    DifferentialEvent::Merge(event) => event.complete.map_or(0, |length| {
+                        length as isize - event.length1 as isize - event.length2 as isize
+                    }),
+                    DifferentialEvent::Drop(event) => -(event.length as isize),
+                    _ => 0,
+                };
+                let ArrangementSize { current, peak } = logged.get();
+                logged.set(ArrangementSize {
+                    current: current + delta,
+                    peak: peak.max(current + delta),
+                });
+            }
+        },
+    );
+    size
+}
@@ -0,0 +1,302 @@
+//! Reclocking of newline-delimited records as they arrive.
+//!
+//! Every line of the input is a JSON object that either introduces a record, in the format of the
+//! records of a [`crate::scenario::Scenario`], or advances the frontier of the source to the
+//! `IntoTime`s it names:
+//!
+//! ```json
+//! { "advance": ["B", "C", "D"] }
+//! { "data": "data", "from_ts": 0, "diff": 2 }
+//! { "advance": ["G"] }
+//! ```
+//!
+//! The records of every partition must arrive in increasing order of offset. A record that does
+//! not list a frontier is reclocked into the current frontier of the source, and one that does
+//! must list times beyond it. The input of the reclocker follows the source frontier, so the
+//! reclocked updates at a time are complete once the source has moved past it.
+//!
+//! With a [`RemapLog`] the upper of the source is bound to every time of its frontier whenever the
+//! frontier advances, and records at offsets that are already bound are reclocked into the
+//! frontier of their bindings instead of the current one. Replaying the input after a restart
+//! then reclocks them the same way, even if the frontier advances at different points of the
+//! input: the input of the reclocker is held back at the times of the logged bindings until the
+//! source has replayed every offset they bind, so the replayed records can still be introduced at
+//! those times once the source frontier has moved past them. Records that arrive before the
+//! frontier is first advanced are not bound, as the minimum `IntoTime` has no name to bind them
+//! to.
+
+use std::io::{self, BufRead};
+use std::thread;
+use std::time::Duration;
+
+use differential_dataflow::lattice::Lattice;
+use serde::Deserialize;
+use timely::communication::Allocate;
+use timely::progress::{Antichain, Timestamp};
+use timely::worker::Worker;
+
+use crate::harness::{Pipeline, Updates};
+use crate::offsets::{self, Offset, SourceUpper};
+use crate::remap::RemapLog;
+use crate::scenario::{Binding, Record};
+use crate::stream::Reclocker;
+
+/// How long to wait for a followed file to grow before reading it again.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A line that advances the frontier of the source.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Advance {
+    advance: Vec<String>,
+}
+
+/// Reclocks the records of a newline-delimited input, one line at a time.
+pub struct Ingest<T, F>
+where
+    T: Timestamp + Lattice,
+{
+    reclocker: Reclocker<String, Offset, T, i64>,
+    frontier: Antichain<T>,
+    /// The names the source frontier was advanced to, if it has been advanced
+    names: Option<Vec<String>>,
+    /// The offset of the next record of every partition
+    upper: SourceUpper,
+    log: Option<RemapLog>,
+    /// The bindings of the log, resolved to times
+    bindings: Vec<(SourceUpper, T)>,
+    /// The bindings of the log whose offsets have not all been replayed yet
+    held: Vec<(SourceUpper, T)>,
+    element: F,
+}
+
+impl<T, F> Ingest<T, F>
+where
+    T: Timestamp + Lattice,
+    F: Fn(&str) -> Result<T, String>,
+{
+    /// Builds a reclock dataflow on `worker`, looking up the times that the input names with
+    /// `element`. The source frontier starts out at the minimum `IntoTime`.
+    pub fn new<A: Allocate>(worker: &mut Worker<A>, element: F) -> Self {
+        Self {
+            reclocker: Reclocker::new(worker, Pipeline::Reduce),
+            frontier: Antichain::from_elem(T::minimum()),
+            names: None,
+            upper: SourceUpper::default(),
+            log: None,
+            bindings: vec![],
+            held: vec![],
+            element,
+        }
+    }
+
+    /// Reclocks the records at offsets that `log` binds into the frontier of their bindings, and
+    /// records the bindings of the source in it from now on.
+    ///
+    /// The reclocked updates at the times of the bindings are held back until the source has
+    /// replayed every offset the bindings cover.
+    pub fn with_remap_log(mut self, log: RemapLog) -> Result<Self, String> {
+        self.bindings = log
+            .bindings()
+            .iter()
+            .map(|binding| Ok((binding.upper.clone(), (self.element)(&binding.into_ts)?)))
+            .collect::<Result<_, String>>()?;
+        self.held = self.bindings.clone();
+        self.log = Some(log);
+        Ok(self)
+    }
+
+    /// The frontier of the source.
+    pub fn frontier(&self) -> &Antichain<T> {
+        &self.frontier
+    }
+
+    /// Handles a line of input and returns the reclocked updates at the times it completed.
+    /// Blank lines are ignored.
+    pub fn line<A: Allocate>(
+        &mut self,
+        worker: &mut Worker<A>,
+        line: &str,
+    ) -> Result<Updates<String, Offset, T, i64>, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(vec![]);
+        }
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+        if value.get("advance").is_some() {
+            let Advance { advance } =
+                serde_json::from_value(value).map_err(|err| err.to_string())?;
+            let frontier = self.resolve(&advance)?;
+            self.advance(frontier, advance)?;
+            Ok(self.reclocker.step(worker))
+        } else {
+            let record: Record = serde_json::from_value(value).map_err(|err| err.to_string())?;
+            let offset = (record.partition, record.from_ts);
+            if let Some(upper) = self.upper.0.get(&record.partition) {
+                if record.from_ts < *upper {
+                    return Err(format!(
+                        "offset {} of partition {} is behind the partition, which is at offset {upper}",
+                        record.from_ts, record.partition
+                    ));
+                }
+            }
+            let frontier = if record.frontier.is_empty() {
+                // Bound offsets are only replayed while their bindings are held, so the reclocker
+                // accepts them even if the source frontier has moved past their bindings
+                let bound = offsets::frontier(&self.bindings, &offset);
+                if bound.is_empty() {
+                    self.frontier.clone()
+                } else {
+                    bound
+                }
+            } else {
+                let frontier = self.resolve(&record.frontier)?;
+                self.check_beyond(&frontier)?;
+                frontier
+            };
+            let next = record
+                .from_ts
+                .checked_add(1)
+                .ok_or_else(|| format!("offset {} is too large", record.from_ts))?;
+            self.reclocker
+                .insert(((record.data, offset, record.diff), frontier))?;
+            self.upper.0.insert(record.partition, next);
+            if self.release() {
+                self.reclocker.advance(&self.input_frontier())?;
+                Ok(self.reclocker.step(worker))
+            } else {
+                Ok(vec![])
+            }
+        }
+    }
+
+    /// Closes the source and returns the reclocked updates at the remaining times.
+    pub fn finish<A: Allocate>(
+        mut self,
+        worker: &mut Worker<A>,
+    ) -> Updates<String, Offset, T, i64> {
+        self.reclocker.close();
+        self.reclocker.step(worker)
+    }
+
+    /// Advances the source frontier, and the input of the reclocker with it, to the times that
+    /// `names` resolved to. An empty frontier closes the source, although the reclocker stays
+    /// open while bindings are held.
+    fn advance(&mut self, frontier: Antichain<T>, names: Vec<String>) -> Result<(), String> {
+        self.check_beyond(&frontier)?;
+        // The records that were reclocked into the frontier that is left behind must be bound
+        // before any of their updates are released
+        if let (Some(log), Some(left)) = (&mut self.log, &self.names) {
+            if !self.upper.0.is_empty() {
+                for name in left {
+                    let upper = self.upper.clone();
+                    let into_ts = (self.element)(name)?;
+                    log.append(Binding {
+                        upper: upper.clone(),
+                        into_ts: name.clone(),
+                    })?;
+                    if !self.bindings.contains(&(upper.clone(), into_ts.clone())) {
+                        self.bindings.push((upper, into_ts));
+                    }
+                }
+            }
+        }
+        self.frontier = frontier;
+        self.names = Some(names);
+        self.release();
+        self.reclocker.advance(&self.input_frontier())
+    }
+
+    /// Stops holding back the bindings whose offsets have all been replayed, and returns whether
+    /// there were any.
+    fn release(&mut self) -> bool {
+        let upper = &self.upper;
+        let held = self.held.len();
+        self.held.retain(|(bound, _)| {
+            !bound
+                .0
+                .iter()
+                .all(|(partition, offset)| upper.0.get(partition) >= Some(offset))
+        });
+        self.held.len() < held
+    }
+
+    /// The frontier the input of the reclocker is held back at, which is the source frontier
+    /// together with the times of the bindings that are held.
+    fn input_frontier(&self) -> Antichain<T> {
+        self.frontier
+            .elements()
+            .iter()
+            .chain(self.held.iter().map(|(_, into_ts)| into_ts))
+            .cloned()
+            .collect()
+    }
+
+    /// Checks that every time of `frontier` is beyond the source frontier.
+    fn check_beyond(&self, frontier: &Antichain<T>) -> Result<(), String> {
+        match frontier
+            .elements()
+            .iter()
+            .find(|time| !self.frontier.less_equal(time))
+        {
+            Some(time) => Err(format!(
+                "{time:?} is not beyond the source frontier {:?}",
+                self.frontier.elements()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn resolve(&self, names: &[String]) -> Result<Antichain<T>, String> {
+        names.iter().map(|name| (self.element)(name)).collect()
+    }
+}
+
+/// The lines of `reader`, without their line endings.
+///
+/// If `follow` is set the lines keep coming as the reader grows, like `tail -f`, and the iterator
+/// never ends. Otherwise it ends with the last line, even if that is not terminated.
+pub fn lines<R: BufRead>(reader: R, follow: bool) -> Lines<R> {
+    Lines {
+        reader,
+        follow,
+        partial: String::new(),
+    }
+}
+
+/// An iterator over the lines of a reader that is possibly still growing.
+pub struct Lines<R> {
+    reader: R,
+    follow: bool,
+    /// The part of the next line that has been read so far
+    partial: String,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_line(&mut self.partial) {
+                Err(err) => return Some(Err(err)),
+                Ok(_) if self.partial.ends_with('\n') => {
+                    let mut line = std::mem::take(&mut self.partial);
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                    return Some(Ok(line));
+                }
+                Ok(0) if !self.follow => {
+                    if self.partial.is_empty() {
+                        return None;
+                    }
+                    return Some(Ok(std::mem::take(&mut self.partial)));
+                }
+                // The writer has not finished the line yet, or not started the next one
+                Ok(0) => thread::sleep(POLL_INTERVAL),
+                Ok(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Checks that a timestamp type obeys the laws of partial orders and lattices.
+//!
+//! Hand written `less_equal`, `join` and `meet` implementations are easy to get wrong, and a
+//! mistake usually shows up as data that is silently double counted. The checks in this module
+//! look for a counterexample either exhaustively, for types with few enough elements, or on
+//! randomly sampled elements.
+
+use std::cell::Cell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use differential_dataflow::lattice::Lattice;
+
+/// A law that partial orders and lattices must obey.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Law {
+    Reflexivity,
+    Antisymmetry,
+    Transitivity,
+    JoinDefined,
+    MeetDefined,
+    JoinUpperBound,
+    JoinLeastUpperBound,
+    MeetLowerBound,
+    MeetGreatestLowerBound,
+    JoinCommutativity,
+    MeetCommutativity,
+    JoinAssociativity,
+    MeetAssociativity,
+    JoinIdempotence,
+    MeetIdempotence,
+    JoinAbsorption,
+    MeetAbsorption,
+    Distributivity,
+}
+
+impl fmt::Display for Law {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let law = match self {
+            Law::Reflexivity => "a <= a",
+            Law::Antisymmetry => "a <= b and b <= a implies a == b",
+            Law::Transitivity => "a <= b and b <= c implies a <= c",
+            Law::JoinDefined => "join(a, b) does not panic",
+            Law::MeetDefined => "meet(a, b) does not panic",
+            Law::JoinUpperBound => "a <= join(a, b) and b <= join(a, b)",
+            Law::JoinLeastUpperBound => "a <= c and b <= c implies join(a, b) <= c",
+            Law::MeetLowerBound => "meet(a, b) <= a and meet(a, b) <= b",
+            Law::MeetGreatestLowerBound => "c <= a and c <= b implies c <= meet(a, b)",
+            Law::JoinCommutativity => "join(a, b) == join(b, a)",
+            Law::MeetCommutativity => "meet(a, b) == meet(b, a)",
+            Law::JoinAssociativity => "join(join(a, b), c) == join(a, join(b, c))",
+            Law::MeetAssociativity => "meet(meet(a, b), c) == meet(a, meet(b, c))",
+            Law::JoinIdempotence => "join(a, a) == a",
+            Law::MeetIdempotence => "meet(a, a) == a",
+            Law::JoinAbsorption => "join(a, meet(a, b)) == a",
+            Law::MeetAbsorption => "meet(a, join(a, b)) == a",
+            Law::Distributivity => "meet(a, join(b, c)) == join(meet(a, b), meet(a, c))",
+        };
+        f.write_str(law)
+    }
+}
+
+/// A counterexample to a law, given as the elements bound to `a`, `b` and `c`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation<T> {
+    pub law: Law,
+    pub elements: Vec<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for Violation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` does not hold for", self.law)?;
+        for (name, element) in ["a", "b", "c"].iter().zip(&self.elements) {
+            write!(f, " {name} = {element:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks every law against every combination of `elements`, which should be all the elements of
+/// the type. Returns the first counterexample found.
+pub fn check_exhaustive<T: Lattice + Clone + Eq>(elements: &[T]) -> Result<(), Violation<T>> {
+    for a in elements {
+        for b in elements {
+            for c in elements {
+                check(a, b, c)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks every law against `rounds` combinations of elements drawn from `sample`. Returns the
+/// first counterexample found.
+pub fn check_sampled<T, F>(mut sample: F, rounds: usize) -> Result<(), Violation<T>>
+where
+    T: Lattice + Clone + Eq,
+    F: FnMut() -> T,
+{
+    for _ in 0..rounds {
+        let (a, b, c) = (sample(), sample(), sample());
+        check(&a, &b, &c)?;
+    }
+    Ok(())
+}
+
+/// Checks that the lattice is distributive on every combination of `elements`. Distributivity is
+/// not a law of all lattices, so it is not part of the other checks. Returns the first
+/// counterexample found.
+pub fn check_distributive<T: Lattice + Clone + Eq>(elements: &[T]) -> Result<(), Violation<T>> {
+    for a in elements {
+        for b in elements {
+            for c in elements {
+                if a.meet(&b.join(c)) != a.meet(b).join(&a.meet(c)) {
+                    return Err(Violation {
+                        law: Law::Distributivity,
+                        elements: vec![a.clone(), b.clone(), c.clone()],
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+thread_local! {
+    /// Whether panics on this thread are expected and should not be reported.
+    static QUIET: Cell<bool> = Cell::new(false);
+}
+
+/// Runs `f` and catches its panic, if any, without the panic hook reporting it.
+///
+/// The hook is process wide, so it is wrapped once in a hook that stays silent only on threads
+/// that are running `quietly`, and reports panics everywhere else as before.
+fn quietly<R>(f: impl FnOnce() -> R) -> std::thread::Result<R> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let report = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET.with(Cell::get) {
+                report(info);
+            }
+        }));
+    });
+    let quiet = QUIET.with(|cell| cell.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    QUIET.with(|cell| cell.set(quiet));
+    result
+}
+
+/// Checks every law for one combination of elements.
+fn check<T: Lattice + Clone + Eq>(a: &T, b: &T, c: &T) -> Result<(), Violation<T>> {
+    let violation = |law, elements: &[&T]| Violation {
+        law,
+        elements: elements.iter().map(|&t| t.clone()).collect(),
+    };
+    let ensure = |holds: bool, law, elements: &[&T]| {
+        if holds {
+            Ok(())
+        } else {
+            Err(violation(law, elements))
+        }
+    };
+    // Incomplete implementations tend to panic on the pairs they forgot about, which we report
+    // like any other counterexample.
+    let join =
+        |x: &T, y: &T| quietly(|| x.join(y)).map_err(|_| violation(Law::JoinDefined, &[x, y]));
+    let meet =
+        |x: &T, y: &T| quietly(|| x.meet(y)).map_err(|_| violation(Law::MeetDefined, &[x, y]));
+
+    ensure(a.less_equal(a), Law::Reflexivity, &[a])?;
+    ensure(
+        !(a.less_equal(b) && b.less_equal(a)) || a == b,
+        Law::Antisymmetry,
+        &[a, b],
+    )?;
+    ensure(
+        !(a.less_equal(b) && b.less_equal(c)) || a.less_equal(c),
+        Law::Transitivity,
+        &[a, b, c],
+    )?;
+
+    let a_join_b = join(a, b)?;
+    let a_meet_b = meet(a, b)?;
+    ensure(
+        a.less_equal(&a_join_b) && b.less_equal(&a_join_b),
+        Law::JoinUpperBound,
+        &[a, b],
+    )?;
+    ensure(
+        !(a.less_equal(c) && b.less_equal(c)) || a_join_b.less_equal(c),
+        Law::JoinLeastUpperBound,
+        &[a, b, c],
+    )?;
+    ensure(
+        a_meet_b.less_equal(a) && a_meet_b.less_equal(b),
+        Law::MeetLowerBound,
+        &[a, b],
+    )?;
+    ensure(
+        !(c.less_equal(a) && c.less_equal(b)) || c.less_equal(&a_meet_b),
+        Law::MeetGreatestLowerBound,
+        &[a, b, c],
+    )?;
+
+    ensure(a_join_b == join(b, a)?, Law::JoinCommutativity, &[a, b])?;
+    ensure(a_meet_b == meet(b, a)?, Law::MeetCommutativity, &[a, b])?;
+    ensure(
+        join(&a_join_b, c)? == join(a, &join(b, c)?)?,
+        Law::JoinAssociativity,
+        &[a, b, c],
+    )?;
+    ensure(
+        meet(&a_meet_b, c)? == meet(a, &meet(b, c)?)?,
+        Law::MeetAssociativity,
+        &[a, b, c],
+    )?;
+    ensure(join(a, a)? == *a, Law::JoinIdempotence, &[a])?;
+    ensure(meet(a, a)? == *a, Law::MeetIdempotence, &[a])?;
+    ensure(join(a, &a_meet_b)? == *a, Law::JoinAbsorption, &[a, b])?;
+    ensure(meet(a, &a_join_b)? == *a, Law::MeetAbsorption, &[a, b])?;
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Reclocking of differential collections into partially ordered timestamps.
+
+pub mod dot;
+pub mod explain;
+pub mod finite;
+pub mod harness;
+pub mod ingest;
+pub mod laws;
+pub mod offsets;
+pub mod oracle;
+pub mod order;
+pub mod product;
+pub mod reclock;
+pub mod remap;
+pub mod scenario;
+pub mod sink;
+pub mod stream;
+
+pub use offsets::ReclockOffsetsExt;
+pub use reclock::{
+    expand_frontiers, inclusion_exclusion, reclock_corrections, reclock_direct, reclock_expanded,
+    reclock_frontiers, reclock_idempotent, reclock_record, remap_frontiers, Idempotent, ReclockExt,
+    INCLUSION_EXCLUSION_MAX_WIDTH,
+};
@@ -1,219 +1,473 @@
-use differential_dataflow::difference::Abelian;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::rc::Rc;
+
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use differential_dataflow::consolidation::consolidate_updates;
+use differential_dataflow::input::Input;
 use differential_dataflow::lattice::Lattice;
-use differential_dataflow::operators::arrange::ArrangeBySelf;
-use differential_dataflow::operators::Reduce;
-use differential_dataflow::AsCollection;
-use differential_dataflow::Collection;
+use dogsdogsdogs::altneu::AltNeu;
 use timely::dataflow::operators::probe::Handle;
-use timely::dataflow::operators::UnorderedInput;
-use timely::dataflow::Scope;
-use timely::progress::{Antichain, Timestamp};
+use timely::dataflow::operators::{Concat, Exchange, Inspect, Map, Probe};
+use timely::progress::Timestamp;
 
-use dogsdogsdogs::altneu::AltNeu;
-use dogsdogsdogs::calculus::Integrate;
+use demo_reclock_reduce::harness::Updates;
+use demo_reclock_reduce::ingest::{self, Ingest};
+use demo_reclock_reduce::offsets::Offset;
+use demo_reclock_reduce::oracle::{self, ReclockedRecord};
+use demo_reclock_reduce::remap::RemapLog;
+use demo_reclock_reduce::scenario::{Record, Resolved, Scenario, TimeDomain};
+use demo_reclock_reduce::sink::{self, Kind, Line, Side};
+use demo_reclock_reduce::{dot, expand_frontiers, explain, harness, product, reclock_expanded};
 
-use order::Time;
+type FromTime = Offset;
 
-/// Reclocks an FromTime collection record into AltNeu<IntoTime> collection records using the
-/// `IntoTime` `frontier`.
+/// The scenario that runs when no scenario file is given.
 ///
-/// Each record is made visible at `Alt(into_ts)` for each `into_ts` in frontier and then retracted
-/// at the the combined `Neu(Lattice::join(..))` of all the `into_ts` in `frontier.
-fn reclock_record<D, R, FromTime, IntoTime>(
-    (data, time, diff): (D, FromTime, R),
-    frontier: Antichain<IntoTime>,
-) -> Vec<((D, FromTime, R), AltNeu<IntoTime>, R)>
-where
-    FromTime: Timestamp,
-    IntoTime: Timestamp + Lattice,
-    D: timely::Data,
-    R: Abelian,
-{
-    // This is the original triplet that will become the data of the new collection
-    let orig_record = (data, time, diff.clone());
+/// It pretends that there is a record "data" that was reclocked into the Time time domain and it
+/// is supposed to be visible at timestamps B, C, D. The goal of the dataflow is to ensure that the
+/// "data" record is never double counted as the Time lattice joins.
+///
+/// The way to do that is to generate the following corrective actions:
+/// ("data", E, -2)
+/// ("data", F, -2)
+/// ("data", G, -2) <--
+/// ("data", G, 2)  <-- the last two will cancel out
+///
+/// The `explain` command prints these actions for any record and frontier.
+const DEMO_SCENARIO: &str = include_str!("../scenarios/demo.json");
+
+/// Reclocks source records into a partially ordered time domain.
+#[derive(Parser)]
+#[command(disable_help_flag = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+    /// Print help
+    #[arg(long, global = true, action = ArgAction::Help)]
+    help: Option<bool>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run a scenario through the reclock dataflow and print the reclocked records
+    #[command(disable_help_flag = true)]
+    Run(RunArgs),
+    /// Run a scenario through the reclock dataflow and check its output for double counting
+    #[command(disable_help_flag = true)]
+    Verify(RunArgs),
+    /// Print the updates that reclocking generates for every record of a scenario
+    #[command(disable_help_flag = true)]
+    Explain(RecordsArgs),
+    /// Print the lattice of a scenario as a Graphviz diagram, annotated with the multiplicities of
+    /// the reclocked records
+    #[command(disable_help_flag = true)]
+    Dot(RecordsArgs),
+    /// Reclock newline-delimited records as they are read from stdin or a file, printing the
+    /// reclocked records as their times complete
+    #[command(disable_help_flag = true)]
+    Ingest(IngestArgs),
+}
+
+#[derive(clap::Args)]
+struct ScenarioArgs {
+    /// The scenario file to load instead of the built-in demo scenario
+    scenario: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct RecordsArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+    /// Use a single record reclocked into these times of the scenario instead
+    #[arg(long, num_args = 1..)]
+    frontier: Option<Vec<String>>,
+    /// The diff of the record given with --frontier
+    #[arg(
+        long,
+        default_value_t = 1,
+        requires = "frontier",
+        allow_negative_numbers = true
+    )]
+    diff: i64,
+}
+
+#[derive(clap::Args)]
+struct IngestArgs {
+    // Only the time domain of the scenario is used, its records are ignored
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+    /// The file to read records from instead of stdin
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+    /// Keep reading the input file as it grows
+    #[arg(short, long, requires = "input")]
+    follow: bool,
+    /// A file to persist the remap bindings of the source in, which are reused when the input is
+    /// ingested again
+    #[arg(long)]
+    remap_log: Option<PathBuf>,
+    /// The format of the printed records
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    #[command(flatten)]
+    scenario: ScenarioArgs,
+    #[command(flatten)]
+    timely: TimelyArgs,
+    /// The format of the printed records
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Also print the original records as they enter the dataflow
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+}
 
-    let mut updates = vec![];
+/// The worker and process configuration, which is forwarded to timely.
+#[derive(clap::Args)]
+struct TimelyArgs {
+    /// The number of worker threads in each process
+    #[arg(short = 'w', long, default_value_t = 1)]
+    workers: usize,
+    /// The number of processes
+    #[arg(short = 'n', long, default_value_t = 1)]
+    processes: usize,
+    /// The index of this process
+    #[arg(short = 'p', long, default_value_t = 0)]
+    process: usize,
+    /// A file with the address of every process, one per line
+    #[arg(short = 'h', long)]
+    hostfile: Option<PathBuf>,
+}
 
-    // First make the record visible at the indicated times and keep track how many copies we
-    // introduce
-    let mut total_diff = R::zero();
-    for into_ts in frontier.iter().cloned() {
-        total_diff.plus_equals(&diff);
-        updates.push((orig_record.clone(), AltNeu::alt(into_ts), diff.clone()));
+impl TimelyArgs {
+    /// Formats the configuration the way `timely::execute_from_args` expects it.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-w".to_owned(),
+            self.workers.to_string(),
+            "-n".to_owned(),
+            self.processes.to_string(),
+            "-p".to_owned(),
+            self.process.to_string(),
+        ];
+        if let Some(hostfile) = &self.hostfile {
+            args.push("-h".to_owned());
+            args.push(hostfile.display().to_string());
+        }
+        args
     }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Debug formatted tuples
+    Text,
+    /// One JSON object per update, consolidated per completed timestamp
+    Json,
+}
 
-    // And then make the record invisible at the join of all the indicated times
-    let mut neu_into_ts = IntoTime::minimum();
-    for into_ts in frontier.iter() {
-        neu_into_ts = neu_into_ts.join(into_ts);
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let result = match args.command {
+        Command::Run(args) => run(args, false),
+        Command::Verify(args) => run(args, true),
+        Command::Explain(args) => explain(args),
+        Command::Dot(args) => dot(args),
+        Command::Ingest(args) => ingest(args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
     }
-    updates.push((orig_record, AltNeu::neu(neu_into_ts), total_diff.negate()));
+}
 
-    updates
+/// Loads the scenario, or the built-in demo scenario if none is given.
+fn load(args: &ScenarioArgs) -> Result<Scenario, String> {
+    match &args.scenario {
+        Some(path) => Scenario::load(path),
+        None => Scenario::parse(DEMO_SCENARIO),
+    }
 }
 
-type FromTime = u64;
+/// Runs the scenario through the reclock dataflow, optionally verifying its output.
+fn run(args: RunArgs, verify: bool) -> Result<(), String> {
+    match load(&args.scenario)?.resolve()? {
+        Resolved::Finite { elements, records } => run_with(args, verify, elements, records),
+        Resolved::Product { elements, records } => run_with(args, verify, elements, records),
+    }
+}
 
-fn main() {
-    timely::execute_from_args(std::env::args().skip(2), move |worker| {
+fn run_with<T>(
+    args: RunArgs,
+    verify: bool,
+    elements: Vec<T>,
+    records: Vec<ReclockedRecord<String, FromTime, i64, T>>,
+) -> Result<(), String>
+where
+    T: Timestamp + Lattice,
+{
+    let (format, verbose) = (args.format, args.verbose);
+
+    let guards = timely::execute_from_args(args.timely.to_args().into_iter(), move |worker| {
         let mut probe = Handle::new();
+        let output = Rc::new(RefCell::new(Vec::new()));
 
-        let (mut handle, cap) = worker.dataflow::<_, _, _>(|scope| {
-            scope.scoped::<AltNeu<Time>, _, _>("Reclock", |inner| {
-                let ((handle, cap), stream) = inner.new_unordered_input();
-
-                let source: Collection<_, (String, FromTime, i64), i64> = stream.as_collection();
-
-                source
-                    .inspect(|record| println!("original record {record:?}"))
-                    .arrange_by_self()
-                    .reduce(|(_data, _from_ts, diff), _input, output| {
-                        // At any timestamp that this record has copies at we must re-assert that
-                        // it has its original diff.
-                        output.push(((), *diff));
-                    })
-                    .integrate()
-                    .map(|record| record.0 .0)
-                    .inspect(|record| println!("reclocked record {record:?}"))
-                    .probe_with(&mut probe);
-
-                (handle, cap)
-            })
+        let mut input = worker.dataflow::<T, _, _>(|scope| {
+            let (input, source) = scope.new_collection::<((String, FromTime), Vec<T>), i64>();
+
+            if verbose > 0 {
+                source.inspect(|record| println!("original record {record:?}"));
+            }
+
+            let expanded = expand_frontiers(&source);
+            // Gather the output on the first worker so that it can be printed and verified in one
+            // place
+            let reclocked = reclock_expanded(&expanded).inner.exchange(|_| 0);
+
+            if let Format::Json = format {
+                // Both kinds of updates go through a single sink so that their lines are written
+                // out in one deterministic order
+                let original = expanded.inner.map(|(record, time, diff)| {
+                    let (((data, _frontier, _time), from_ts, _diff), into_ts) = record;
+                    let into_ts = into_ts.join(&AltNeu::alt(time.clone()));
+                    let side = if into_ts.neu { Side::Neu } else { Side::Alt };
+                    let into_ts = format!("{:?}", into_ts.time);
+                    (
+                        (Kind::Original, data, from_ts, into_ts, Some(side)),
+                        time,
+                        diff,
+                    )
+                });
+                let lines = reclocked.map(|((data, from_ts), time, diff)| {
+                    let into_ts = format!("{time:?}");
+                    ((Kind::Reclocked, data, from_ts, into_ts, None), time, diff)
+                });
+                let lines = original.concat(&lines).exchange(|_| 0);
+                sink::json_lines(&lines, io::stdout(), |line, _time, diff| {
+                    let (kind, data, from_ts, into_ts, side) = line;
+                    Line {
+                        kind,
+                        data,
+                        from_ts,
+                        into_ts,
+                        side,
+                        diff,
+                    }
+                });
+            }
+
+            let output = Rc::clone(&output);
+            reclocked
+                .inspect(move |((data, _from_ts), time, diff)| {
+                    output
+                        .borrow_mut()
+                        .push((data.clone(), time.clone(), *diff))
+                })
+                .probe_with(&mut probe);
+
+            input
         });
 
-        // We will pretend that there is a record "data" that was reclocked into the Time time
-        // domain and it is supposed to be visible at timestamps B, C, D.
-        //
-        // The goal of the dataflow above is to ensure that the "data" record is never double
-        // counted as the Time lattice joins.
-        //
-        // The way to do that is to generate the following corrective actions:
-        // ("data", E, -2)
-        // ("data", F, -2)
-        // ("data", G, -2) <--
-        // ("data", G, 2)  <-- the last two will cancel out
-        let record = ("data".to_owned(), 0, 2);
-        let frontier = Antichain::from_iter([Time::B, Time::C, Time::D]);
-        for (record, time, diff) in reclock_record(record, frontier) {
-            handle
-                .session(cap.delayed(&time))
-                .give((record, time, diff));
+        // Every worker introduces its share of the records
+        let (index, peers) = (worker.index(), worker.peers());
+        for (i, ((data, from_ts, diff), frontier)) in records.iter().enumerate() {
+            if i % peers == index {
+                let mut frontier = frontier.elements().to_vec();
+                frontier.sort();
+                input.update(((data.clone(), *from_ts), frontier), *diff);
+            }
         }
-        drop(cap);
+        input.close();
         while !probe.done() {
             worker.step();
         }
-    })
-    .unwrap();
-}
-
-/// Defines a partialy ordered time that looks like this:
-///    ,--B----E.
-///   /       /  \
-///  /       /    \
-/// A-------C------G
-///  \       \    /
-///   \       \  /
-///    `--D----F'
-mod order {
-    use differential_dataflow::lattice::Lattice;
-    use serde::{Deserialize, Serialize};
-    use timely::order::PartialOrder;
-    use timely::progress::timestamp::{PathSummary, Refines, Timestamp};
-
-    #[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
-    pub enum Time {
-        A,
-        B,
-        C,
-        D,
-        E,
-        F,
-        G,
-    }
 
-    impl Timestamp for Time {
-        type Summary = ();
-
-        fn minimum() -> Self {
-            Self::A
+        if index != 0 {
+            return Ok(());
         }
-    }
-
-    impl PathSummary<Time> for () {
-        fn results_in(&self, src: &Time) -> Option<Time> {
-            Some(*src)
+        // The output arrives in an order that depends on the scheduling of the workers
+        let mut output = output.take();
+        consolidate_updates(&mut output);
+        if let Format::Text = format {
+            for update in output.iter() {
+                println!("reclocked record {update:?}");
+            }
         }
-        fn followed_by(&self, _other: &Self) -> Option<Self> {
-            Some(())
+        if verify {
+            oracle::verify(&records, &output, &elements).map_err(|report| report.to_string())?;
+            println!("ok: no record is double counted at any time");
         }
+        Ok(())
+    })?;
+
+    for result in guards.join() {
+        result??;
     }
+    Ok(())
+}
 
-    impl Refines<()> for Time {
-        fn to_inner(_other: ()) -> Self {
-            Time::A
+/// Prints the updates of every step of reclocking each record, and the multiplicity it ends up
+/// with at every element of the lattice.
+fn explain(args: RecordsArgs) -> Result<(), String> {
+    match load_records(&args)? {
+        Resolved::Finite { elements, records } => explain_with(elements, records),
+        Resolved::Product { elements, records } => explain_with(elements, records),
+    }
+}
+
+fn explain_with<T>(
+    elements: Vec<T>,
+    records: Vec<ReclockedRecord<String, FromTime, i64, T>>,
+) -> Result<(), String>
+where
+    T: Timestamp + Lattice,
+{
+    for (i, (record, frontier)) in records.into_iter().enumerate() {
+        if i > 0 {
+            println!();
         }
-        fn to_outer(self) {}
-        fn summarize(_path: Self::Summary) -> <() as Timestamp>::Summary {}
+        println!("{record:?} reclocked into {:?}:", frontier.elements());
+        print!("{}", explain::explain(record, frontier, &elements));
     }
+    Ok(())
+}
 
-    impl PartialOrder for Time {
-        fn less_equal(&self, other: &Self) -> bool {
-            matches!((self, other), |(Time::A, _)| (
-                Time::B,
-                Time::B | Time::E | Time::G
-            ) | (
-                Time::C,
-                Time::C | Time::E | Time::F | Time::G
-            ) | (
-                Time::D,
-                Time::D | Time::F | Time::G
-            ) | (
-                Time::E,
-                Time::E | Time::G
-            ) | (
-                Time::F,
-                Time::F | Time::G
-            ) | (Time::G, Time::G))
+/// Prints the Hasse diagram of the lattice with the multiplicities of the reclocked records at
+/// every element, highlighting the elements their frontiers consist of.
+fn dot(args: RecordsArgs) -> Result<(), String> {
+    match load_records(&args)? {
+        Resolved::Finite { elements, records } => dot_with(elements, records),
+        Resolved::Product { elements, records } => dot_with(elements, records),
+    }
+}
+
+fn dot_with<T>(
+    elements: Vec<T>,
+    records: Vec<ReclockedRecord<String, FromTime, i64, T>>,
+) -> Result<(), String>
+where
+    T: Timestamp + Lattice,
+{
+    let mut frontier: Vec<_> = records
+        .iter()
+        .flat_map(|(_, frontier)| frontier.elements().to_vec())
+        .collect();
+    frontier.sort();
+    frontier.dedup();
+
+    let updates: Vec<_> = harness::reclock(1, records)?
+        .into_iter()
+        .flat_map(|(time, updates)| {
+            updates
+                .into_iter()
+                .map(move |(update, diff)| (update, time.clone(), diff))
+        })
+        .collect();
+    let diagram = dot::hasse_diagram(&elements, &frontier, |element| {
+        oracle::accumulate_at(&updates, element)
+            .into_iter()
+            .map(|((data, from_ts), diff)| format!("{data:?} {from_ts:?}: {diff}"))
+            .collect()
+    });
+    print!("{diagram}");
+    Ok(())
+}
+
+/// Reclocks the records of the input as they arrive, in the time domain of the scenario.
+fn ingest(args: IngestArgs) -> Result<(), String> {
+    let scenario = load(&args.scenario)?;
+    match scenario.time {
+        TimeDomain::Finite => {
+            let lattice = scenario.lattice()?;
+            ingest_with(args, move |name: &str| {
+                lattice
+                    .element(name)
+                    .ok_or_else(|| format!("unknown lattice element {name}"))
+            })
         }
+        TimeDomain::Product => ingest_with(args, product::parse),
     }
+}
+
+fn ingest_with<T, F>(args: IngestArgs, element: F) -> Result<(), String>
+where
+    T: Timestamp + Lattice,
+    F: Fn(&str) -> Result<T, String> + Send + Sync + 'static,
+{
+    let (input, follow, format) = (args.input, args.follow, args.format);
+    let remap_log = args.remap_log;
 
-    impl Lattice for Time {
-        fn join(&self, other: &Self) -> Self {
-            match (self, other) {
-                (min, max) if PartialOrder::less_equal(min, max) => *max,
-                (max, min) if PartialOrder::less_equal(min, max) => *max,
-                (Time::B, Time::D)
-                | (Time::D, Time::B)
-                | (Time::B, Time::F)
-                | (Time::F, Time::B)
-                | (Time::E, Time::D)
-                | (Time::D, Time::E)
-                | (Time::E, Time::F)
-                | (Time::F, Time::E) => Time::G,
-                (Time::B, Time::C) | (Time::C, Time::B) => Time::E,
-                (Time::C, Time::D) | (Time::D, Time::C) => Time::F,
-                _ => unreachable!(),
+    timely::execute_directly(move |worker| {
+        let reader: Box<dyn BufRead> = match &input {
+            Some(path) => {
+                let file = File::open(path)
+                    .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+                Box::new(BufReader::new(file))
             }
+            None => Box::new(io::stdin().lock()),
+        };
+
+        let mut ingest = Ingest::new(worker, element);
+        if let Some(path) = &remap_log {
+            ingest = ingest.with_remap_log(RemapLog::open(path)?)?;
         }
+        for (number, line) in ingest::lines(reader, follow).enumerate() {
+            let line = line.map_err(|err| format!("failed to read input: {err}"))?;
+            let updates = ingest
+                .line(worker, &line)
+                .map_err(|err| format!("line {}: {err}", number + 1))?;
+            print_updates(updates, format);
+        }
+        print_updates(ingest.finish(worker), format);
+        Ok(())
+    })
+}
 
-        fn meet(&self, other: &Self) -> Self {
-            match (self, other) {
-                (min, max) if PartialOrder::less_equal(min, max) => *min,
-                (max, min) if PartialOrder::less_equal(min, max) => *min,
-                (Time::B, Time::C)
-                | (Time::C, Time::B)
-                | (Time::C, Time::D)
-                | (Time::D, Time::C)
-                | (Time::B, Time::D)
-                | (Time::D, Time::B)
-                | (Time::B, Time::F)
-                | (Time::F, Time::B)
-                | (Time::E, Time::D)
-                | (Time::D, Time::E) => Time::A,
-                (Time::E, Time::F) | (Time::F, Time::E) => Time::C,
-                _ => unreachable!(),
+/// Prints reclocked updates in the given format.
+fn print_updates<T: Timestamp>(updates: Updates<String, FromTime, T, i64>, format: Format) {
+    for (time, updates) in updates {
+        for ((data, from_ts), diff) in updates {
+            match format {
+                Format::Text => println!("reclocked record {:?}", (data, time.clone(), diff)),
+                Format::Json => {
+                    let line = Line {
+                        kind: Kind::Reclocked,
+                        data,
+                        from_ts,
+                        into_ts: format!("{time:?}"),
+                        side: None,
+                        diff,
+                    };
+                    let line = serde_json::to_string(&line).expect("failed to serialize line");
+                    println!("{line}");
+                }
             }
         }
     }
 }
+
+/// Loads the records of the scenario, or the single record given on the command line.
+fn load_records(args: &RecordsArgs) -> Result<Resolved, String> {
+    let mut scenario = load(&args.scenario)?;
+    if let Some(frontier) = &args.frontier {
+        scenario.bindings.clear();
+        scenario.records = vec![Record {
+            data: "data".to_owned(),
+            partition: 0,
+            from_ts: 0,
+            diff: args.diff,
+            frontier: frontier.clone(),
+        }];
+    }
+    scenario.resolve()
+}
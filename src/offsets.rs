@@ -0,0 +1,95 @@
+//! Reclocking of partitioned sources with totally ordered offsets, such as Kafka topics.
+//!
+//! A record of such a source is identified by its partition and its offset within that partition.
+//! The progress of the source is described by its upper, the offset of the next record of every
+//! partition, and the remap bindings of the source state which upper had been reached at each
+//! `IntoTime`. A record is reclocked into the minimal `IntoTime`s whose binding upper is beyond
+//! its offset.
+
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+use differential_dataflow::difference::{Abelian, Multiply};
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::{Collection, ExchangeData};
+use serde::{Deserialize, Serialize};
+use timely::dataflow::Scope;
+use timely::progress::{Antichain, Timestamp};
+
+use crate::reclock::{reclock_frontiers, remap_frontiers};
+
+/// The identifier of a partition of a source.
+pub type PartitionId = u32;
+
+/// The position of a record in a partitioned source, which is its `FromTime`.
+pub type Offset = (PartitionId, u64);
+
+/// The upper of a partitioned source, as the offset of the next record of each partition.
+/// Partitions that are not listed have not produced any records yet.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SourceUpper(pub BTreeMap<PartitionId, u64>);
+
+impl SourceUpper {
+    /// Whether the record at `offset` is before this upper, i.e. has been produced by the time
+    /// the source reached it.
+    pub fn is_beyond(&self, (partition, offset): &Offset) -> bool {
+        self.0.get(partition).is_some_and(|upper| offset < upper)
+    }
+}
+
+impl FromIterator<(PartitionId, u64)> for SourceUpper {
+    fn from_iter<I: IntoIterator<Item = (PartitionId, u64)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Computes the `IntoTime` frontier a record at `offset` is reclocked into by `bindings`.
+///
+/// The frontier is empty if no binding is beyond the offset yet.
+pub fn frontier<T: Timestamp>(bindings: &[(SourceUpper, T)], offset: &Offset) -> Antichain<T> {
+    bindings
+        .iter()
+        .filter(|(upper, _)| upper.is_beyond(offset))
+        .map(|(_, into_ts)| into_ts.clone())
+        .collect()
+}
+
+/// Reclocks a collection of `(data, offset)` updates of a partitioned source.
+pub trait ReclockOffsetsExt<G: Scope, D, R> {
+    /// Reclocks every update using the bindings of a `remap` collection, which maps the uppers of
+    /// the source to the `IntoTime`s at which they were reached.
+    ///
+    /// Every binding is split into the uppers of its partitions, so updates are only matched
+    /// against the bindings of their own partition.
+    fn reclock_offsets(
+        &self,
+        remap: &Collection<G, (SourceUpper, G::Timestamp), R>,
+    ) -> Collection<G, D, R>;
+}
+
+impl<G, D, R> ReclockOffsetsExt<G, D, R> for Collection<G, (D, Offset), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: ExchangeData + Hash,
+    R: Abelian + ExchangeData + Hash + Multiply<Output = R> + From<i8>,
+{
+    fn reclock_offsets(
+        &self,
+        remap: &Collection<G, (SourceUpper, G::Timestamp), R>,
+    ) -> Collection<G, D, R> {
+        let remap = remap.flat_map(|(upper, into_ts)| {
+            upper
+                .0
+                .into_iter()
+                .map(move |(partition, upper)| (partition, (upper, into_ts.clone())))
+        });
+        let frontiers = remap_frontiers(
+            self,
+            &remap,
+            |(partition, _offset): &Offset| *partition,
+            |upper: &u64, (_partition, offset): &Offset| offset < upper,
+        );
+        reclock_frontiers(&frontiers).map(|(data, _offset)| data)
+    }
+}
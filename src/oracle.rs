@@ -0,0 +1,123 @@
+//! Checks the output of a reclocking dataflow against the multiplicities it must have.
+//!
+//! A record reclocked into frontier `F` must be present with its original diff at every time
+//! beyond `F` and absent everywhere else, however the elements of `F` join. The oracle computes
+//! these multiplicities directly from the input records and compares them with the accumulated
+//! output of the dataflow at every `IntoTime` of interest.
+
+use std::fmt;
+
+use differential_dataflow::consolidation::consolidate;
+use differential_dataflow::difference::Abelian;
+use timely::order::PartialOrder;
+use timely::progress::Antichain;
+
+/// A source record together with the `IntoTime` frontier it was reclocked into.
+pub type ReclockedRecord<D, FromTime, R, T> = ((D, FromTime, R), Antichain<T>);
+
+/// Accumulates the expected contents of the reclocked collection at `time`.
+pub fn expected_at<D, FromTime, R, T>(
+    records: &[ReclockedRecord<D, FromTime, R, T>],
+    time: &T,
+) -> Vec<(D, R)>
+where
+    D: Ord + Clone,
+    R: Abelian,
+    T: PartialOrder,
+{
+    let mut accum: Vec<_> = records
+        .iter()
+        .filter(|(_, frontier)| frontier.less_equal(time))
+        .map(|((data, _, diff), _)| (data.clone(), diff.clone()))
+        .collect();
+    consolidate(&mut accum);
+    accum
+}
+
+/// Accumulates the contents of a collection at `time` from its `updates`.
+pub fn accumulate_at<D, R, T>(updates: &[(D, T, R)], time: &T) -> Vec<(D, R)>
+where
+    D: Ord + Clone,
+    R: Abelian,
+    T: PartialOrder,
+{
+    let mut accum: Vec<_> = updates
+        .iter()
+        .filter(|(_, t, _)| t.less_equal(time))
+        .map(|(data, _, diff)| (data.clone(), diff.clone()))
+        .collect();
+    consolidate(&mut accum);
+    accum
+}
+
+/// Verifies that the reclocked `output` of `records` has the expected contents at every one of
+/// `times`, returning a report of every time at which it does not.
+pub fn verify<D, FromTime, R, T>(
+    records: &[ReclockedRecord<D, FromTime, R, T>],
+    output: &[(D, T, R)],
+    times: &[T],
+) -> Result<(), Report<D, T, R>>
+where
+    D: Ord + Clone,
+    R: Abelian + Eq,
+    T: PartialOrder + Clone,
+{
+    let mut mismatches = vec![];
+    for time in times {
+        let expected = expected_at(records, time);
+        let actual = accumulate_at(output, time);
+        if expected != actual {
+            let mut difference = actual.clone();
+            difference.extend(
+                expected
+                    .iter()
+                    .map(|(d, r)| (d.clone(), r.clone().negate())),
+            );
+            consolidate(&mut difference);
+            mismatches.push(Mismatch {
+                time: time.clone(),
+                expected,
+                actual,
+                difference,
+            });
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Report { mismatches })
+    }
+}
+
+/// The accumulated contents of a collection at a time where they differ from the expected ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch<D, T, R> {
+    pub time: T,
+    pub expected: Vec<(D, R)>,
+    pub actual: Vec<(D, R)>,
+    /// The updates that would need to be retracted from `actual` to obtain `expected`.
+    pub difference: Vec<(D, R)>,
+}
+
+/// Every time at which the reclocked output did not match the expected contents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report<D, T, R> {
+    pub mismatches: Vec<Mismatch<D, T, R>>,
+}
+
+impl<D: fmt::Debug, T: fmt::Debug, R: fmt::Debug> fmt::Display for Report<D, T, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "reclocked output differs from the expected one at {} time(s):",
+            self.mismatches.len()
+        )?;
+        for mismatch in &self.mismatches {
+            writeln!(f, "  at {:?}:", mismatch.time)?;
+            writeln!(f, "    expected:   {:?}", mismatch.expected)?;
+            writeln!(f, "    actual:     {:?}", mismatch.actual)?;
+            writeln!(f, "    difference: {:?}", mismatch.difference)?;
+        }
+        Ok(())
+    }
+}
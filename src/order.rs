@@ -0,0 +1,122 @@
+//! Defines a partialy ordered time that looks like this:
+//!    ,--B----E.
+//!   /       /  \
+//!  /       /    \
+//! A-------C------G
+//!  \       \    /
+//!   \       \  /
+//!    `--D----F'
+
+use differential_dataflow::lattice::Lattice;
+use serde::{Deserialize, Serialize};
+use timely::order::PartialOrder;
+use timely::progress::timestamp::{PathSummary, Refines, Timestamp};
+
+#[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Time {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+}
+
+impl Time {
+    /// Every element of the lattice.
+    pub const ELEMENTS: [Time; 7] = [
+        Time::A,
+        Time::B,
+        Time::C,
+        Time::D,
+        Time::E,
+        Time::F,
+        Time::G,
+    ];
+}
+
+impl Timestamp for Time {
+    type Summary = ();
+
+    fn minimum() -> Self {
+        Self::A
+    }
+}
+
+impl PathSummary<Time> for () {
+    fn results_in(&self, src: &Time) -> Option<Time> {
+        Some(*src)
+    }
+    fn followed_by(&self, _other: &Self) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Refines<()> for Time {
+    fn to_inner(_other: ()) -> Self {
+        Time::A
+    }
+    fn to_outer(self) {}
+    fn summarize(_path: Self::Summary) -> <() as Timestamp>::Summary {}
+}
+
+impl PartialOrder for Time {
+    fn less_equal(&self, other: &Self) -> bool {
+        matches!((self, other), |(Time::A, _)| (
+            Time::B,
+            Time::B | Time::E | Time::G
+        ) | (
+            Time::C,
+            Time::C | Time::E | Time::F | Time::G
+        ) | (
+            Time::D,
+            Time::D | Time::F | Time::G
+        ) | (
+            Time::E,
+            Time::E | Time::G
+        ) | (
+            Time::F,
+            Time::F | Time::G
+        ) | (Time::G, Time::G))
+    }
+}
+
+impl Lattice for Time {
+    fn join(&self, other: &Self) -> Self {
+        match (self, other) {
+            (min, max) if PartialOrder::less_equal(min, max) => *max,
+            (max, min) if PartialOrder::less_equal(min, max) => *max,
+            (Time::B, Time::D)
+            | (Time::D, Time::B)
+            | (Time::B, Time::F)
+            | (Time::F, Time::B)
+            | (Time::E, Time::D)
+            | (Time::D, Time::E)
+            | (Time::E, Time::F)
+            | (Time::F, Time::E) => Time::G,
+            (Time::B, Time::C) | (Time::C, Time::B) => Time::E,
+            (Time::C, Time::D) | (Time::D, Time::C) => Time::F,
+            _ => unreachable!(),
+        }
+    }
+
+    fn meet(&self, other: &Self) -> Self {
+        match (self, other) {
+            (min, max) if PartialOrder::less_equal(min, max) => *min,
+            (max, min) if PartialOrder::less_equal(min, max) => *min,
+            (Time::B, Time::C)
+            | (Time::C, Time::B)
+            | (Time::C, Time::D)
+            | (Time::D, Time::C)
+            | (Time::B, Time::D)
+            | (Time::D, Time::B)
+            | (Time::B, Time::F)
+            | (Time::F, Time::B)
+            | (Time::E, Time::D)
+            | (Time::D, Time::E) => Time::A,
+            (Time::E, Time::F) | (Time::F, Time::E) => Time::C,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Product ordered `IntoTime`s, such as pairs of an epoch and a sub-epoch.
+//!
+//! A pair is less than or equal to another if both of its coordinates are, so frontiers like
+//! `{(1, 3), (2, 2), (3, 1)}` consist of incomparable pairs whose joins take the largest of each
+//! coordinate. Unlike the elements of a [`crate::finite::FiniteLattice`] there are infinitely many
+//! pairs, but the multiplicities of records reclocked into a set of pairs only change at pairs made
+//! of their coordinates.
+
+use std::collections::BTreeSet;
+
+use timely::order::Product;
+
+/// A pair of product ordered integers.
+pub type Pair = Product<u64, u64>;
+
+/// Parses a pair written as `(outer, inner)`, with the parentheses being optional.
+pub fn parse(name: &str) -> Result<Pair, String> {
+    let invalid = || format!("invalid pair {name}, expected (outer, inner)");
+    let name = name.trim();
+    let name = match name.strip_prefix('(') {
+        Some(name) => name.strip_suffix(')').ok_or_else(invalid)?,
+        None => name,
+    };
+    let (outer, inner) = name.split_once(',').ok_or_else(invalid)?;
+    let outer = outer.trim().parse().map_err(|_| invalid())?;
+    let inner = inner.trim().parse().map_err(|_| invalid())?;
+    Ok(Product::new(outer, inner))
+}
+
+/// Builds every pair made of the coordinates of `pairs` and zero, sorted.
+///
+/// The joins of `pairs` are all part of the grid, so the accumulation of updates at these pairs
+/// determines the accumulation at any other pair, which is that of the largest grid pair below it.
+pub fn grid<'a>(pairs: impl IntoIterator<Item = &'a Pair>) -> Vec<Pair> {
+    let mut outer = BTreeSet::from([0]);
+    let mut inner = BTreeSet::from([0]);
+    for pair in pairs {
+        outer.insert(pair.outer);
+        inner.insert(pair.inner);
+    }
+    outer
+        .iter()
+        .flat_map(|&o| inner.iter().map(move |&i| Product::new(o, i)))
+        .collect()
+}
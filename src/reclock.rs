@@ -0,0 +1,498 @@
+//! Reclocking of source updates into a partially ordered `IntoTime` domain.
+
+use std::hash::Hash;
+
+use differential_dataflow::consolidation::consolidate;
+use differential_dataflow::difference::{Abelian, Multiply, Semigroup};
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::arrange::{ArrangeByKey, ArrangeBySelf, Arranged};
+use differential_dataflow::operators::{JoinCore, Reduce, Threshold};
+use differential_dataflow::trace::TraceReader;
+use differential_dataflow::{AsCollection, Collection, ExchangeData};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Map, Operator};
+use timely::dataflow::Scope;
+use timely::order::PartialOrder;
+use timely::progress::{Antichain, Timestamp};
+
+use dogsdogsdogs::altneu::AltNeu;
+use dogsdogsdogs::calculus::Integrate;
+
+use crate::laws::{self, Violation};
+
+/// Reclocks an FromTime collection record into AltNeu<IntoTime> collection records using the
+/// `IntoTime` `frontier`.
+///
+/// Each record is made visible at `Alt(into_ts)` for each `into_ts` in frontier and then retracted
+/// at the the combined `Neu(Lattice::join(..))` of all the `into_ts` in `frontier.
+pub fn reclock_record<D, R, FromTime, IntoTime>(
+    (data, time, diff): (D, FromTime, R),
+    frontier: Antichain<IntoTime>,
+) -> Vec<((D, FromTime, R), AltNeu<IntoTime>, R)>
+where
+    FromTime: timely::Data,
+    IntoTime: Timestamp + Lattice,
+    D: timely::Data,
+    R: Abelian,
+{
+    // This is the original triplet that will become the data of the new collection
+    let orig_record = (data, time, diff.clone());
+
+    let mut updates = vec![];
+
+    // First make the record visible at the indicated times and keep track how many copies we
+    // introduce
+    let mut total_diff = R::zero();
+    for into_ts in frontier.iter().cloned() {
+        total_diff.plus_equals(&diff);
+        updates.push((orig_record.clone(), AltNeu::alt(into_ts), diff.clone()));
+    }
+
+    // And then make the record invisible at the join of all the indicated times
+    let mut neu_into_ts = IntoTime::minimum();
+    for into_ts in frontier.iter() {
+        neu_into_ts = neu_into_ts.join(into_ts);
+    }
+    updates.push((orig_record, AltNeu::neu(neu_into_ts), total_diff.negate()));
+
+    updates
+}
+
+/// Reclocks a collection of `(data, from_ts)` source updates into the timestamp of its scope.
+pub trait ReclockExt<G: Scope, D, FromTime, R> {
+    /// Reclocks every source update into the `IntoTime` frontier that `bindings` assigns to its
+    /// `FromTime`.
+    ///
+    /// Each update is present with its original diff at every `IntoTime` beyond its frontier and is
+    /// never double counted at the joins of the frontier elements.
+    fn reclock<B>(&self, bindings: B) -> Collection<G, D, R>
+    where
+        B: FnMut(&FromTime) -> Antichain<G::Timestamp> + 'static;
+
+    /// Reclocks every source update using the bindings of a `remap` collection.
+    ///
+    /// A binding `(upper, into_ts)` states that every `FromTime` not beyond the `upper` antichain
+    /// is visible at `into_ts`. Each update is reclocked into the minimal `into_ts` of the bindings
+    /// whose `upper` is beyond its `FromTime`, and is not visible until such a binding exists.
+    ///
+    /// Bindings are keyed by the part of the `FromTime`s they describe, such as a partition of the
+    /// source, and only apply to the updates whose `FromTime` has that `key`.
+    fn reclock_remap<K, F>(
+        &self,
+        remap: &Collection<G, (K, (Vec<FromTime>, G::Timestamp)), R>,
+        key: F,
+    ) -> Collection<G, D, R>
+    where
+        K: ExchangeData + Hash,
+        F: Fn(&FromTime) -> K + 'static,
+        R: Multiply<Output = R> + From<i8>;
+}
+
+impl<G, D, FromTime, R> ReclockExt<G, D, FromTime, R> for Collection<G, (D, FromTime), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: ExchangeData + Hash,
+    FromTime: Timestamp,
+    R: Abelian + ExchangeData + Hash,
+{
+    fn reclock<B>(&self, mut bindings: B) -> Collection<G, D, R>
+    where
+        B: FnMut(&FromTime) -> Antichain<G::Timestamp> + 'static,
+    {
+        let frontiers = self.map(move |(data, from_ts)| {
+            let mut frontier = bindings(&from_ts).elements().to_vec();
+            frontier.sort();
+            ((data, from_ts), frontier)
+        });
+        reclock_frontiers(&frontiers).map(|(data, _from_ts)| data)
+    }
+
+    fn reclock_remap<K, F>(
+        &self,
+        remap: &Collection<G, (K, (Vec<FromTime>, G::Timestamp)), R>,
+        key: F,
+    ) -> Collection<G, D, R>
+    where
+        K: ExchangeData + Hash,
+        F: Fn(&FromTime) -> K + 'static,
+        R: Multiply<Output = R> + From<i8>,
+    {
+        // The update is visible at `into_ts` only if `upper` is beyond its `from_ts`
+        let frontiers = remap_frontiers(self, remap, key, |upper: &Vec<FromTime>, from_ts| {
+            !upper.iter().any(|t| t.less_equal(from_ts))
+        });
+        reclock_frontiers(&frontiers).map(|(data, _from_ts)| data)
+    }
+}
+
+/// Assigns every source update the `IntoTime` frontier described by the bindings of a `remap`
+/// collection.
+///
+/// A binding `(key, (upper, into_ts))` makes every update whose `FromTime` has the given `key` and
+/// for which `visible(upper, from_ts)` holds visible at `into_ts`. Each update is assigned the
+/// minimal `into_ts` of the bindings it is visible at, and has no frontier until such a binding
+/// exists. A binding is in effect whenever its accumulated diff is not zero, whatever that diff
+/// is.
+///
+/// The frontiers are computed once for every distinct `FromTime`, by joining them with the
+/// bindings of their key, and are then joined with the updates by their `FromTime`.
+pub fn remap_frontiers<G, D, FromTime, K, U, R, F, V>(
+    updates: &Collection<G, (D, FromTime), R>,
+    remap: &Collection<G, (K, (U, G::Timestamp)), R>,
+    key: F,
+    visible: V,
+) -> Collection<G, ((D, FromTime), Vec<G::Timestamp>), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    K: ExchangeData + Hash,
+    U: ExchangeData,
+    R: Abelian + ExchangeData + Hash + Multiply<Output = R> + From<i8>,
+    F: Fn(&FromTime) -> K + 'static,
+    V: Fn(&U, &FromTime) -> bool + 'static,
+{
+    let times = updates
+        .map(move |(_data, from_ts)| (key(&from_ts), from_ts))
+        .threshold(|_time, _count| R::from(1))
+        .arrange_by_key();
+    compact(&times);
+    let remap = remap.arrange_by_key();
+    compact(&remap);
+
+    let bindings = times
+        .join_core(&remap, move |_key, from_ts, (upper, into_ts)| {
+            visible(upper, from_ts).then(|| (from_ts.clone(), into_ts.clone()))
+        })
+        .arrange_by_key();
+    compact(&bindings);
+
+    let frontiers = bindings
+        .reduce(|_from_ts, input, output| {
+            // The bindings are in effect whatever their diff is, so the frontier of a `FromTime`
+            // is asserted exactly once
+            let frontier = input
+                .iter()
+                .filter(|(_, count)| !count.is_zero())
+                .map(|(into_ts, _)| (*into_ts).clone());
+            let mut frontier = Antichain::from_iter(frontier).elements().to_vec();
+            if !frontier.is_empty() {
+                frontier.sort();
+                output.push((frontier, R::from(1)));
+            }
+        })
+        .arrange_by_key();
+    compact(&frontiers);
+
+    let updates = updates
+        .map(|(data, from_ts)| (from_ts, data))
+        .arrange_by_key();
+    compact(&updates);
+
+    updates.join_core(&frontiers, |from_ts, data, frontier| {
+        Some(((data.clone(), from_ts.clone()), frontier.clone()))
+    })
+}
+
+/// Advances the compaction frontiers of the trace of `arranged` along with the frontier of its
+/// stream, so that the trace only keeps the distinctions between times that are not yet complete.
+///
+/// The operators reading an arrangement advance the compaction of their own trace handles as they
+/// go, but keeping a handle here makes the trace compact whatever the operators downstream of it
+/// do.
+fn compact<G, Tr>(arranged: &Arranged<G, Tr>)
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    Tr: TraceReader<Time = G::Timestamp> + Clone + 'static,
+{
+    let mut trace = arranged.trace.clone();
+    arranged.stream.sink(Pipeline, "Compact", move |input| {
+        input.for_each(|_time, _batches| {});
+        trace.set_logical_compaction(input.frontier().frontier());
+        trace.set_physical_compaction(input.frontier().frontier());
+    });
+}
+
+/// A source update as reclocked by [`reclock_record`], tagged with the `AltNeu` time it applies
+/// at. The frontier the update was reclocked into and the time it arrived at are kept next to its
+/// data so that distinct updates of the same record are never consolidated together.
+pub type Expanded<D, FromTime, R, T> = (((D, Vec<T>, T), FromTime, R), AltNeu<T>);
+
+/// Reclocks source updates whose `IntoTime` frontier has already been determined.
+///
+/// Every update carries the elements of its frontier next to its data. This is the common core of
+/// the [`ReclockExt`] methods, for callers that assign frontiers on their own, and is the
+/// composition of [`expand_frontiers`] and [`reclock_expanded`].
+///
+/// Retractions are reclocked like any other update, and a record can be updated by retracting it
+/// from the frontier it was reclocked into and inserting it into a different one. An update that
+/// arrives at time `t` is reclocked into the join of `t` with each element of its frontier.
+pub fn reclock_frontiers<G, D, FromTime, R>(
+    updates: &Collection<G, ((D, FromTime), Vec<G::Timestamp>), R>,
+) -> Collection<G, (D, FromTime), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    R: Abelian + ExchangeData + Hash,
+{
+    reclock_expanded(&expand_frontiers(updates))
+}
+
+/// Expands every source update into the `AltNeu` updates that [`reclock_record`] generates for
+/// the join of its time with each element of its frontier.
+///
+/// Identical updates are consolidated first, as the reduce of [`reclock_expanded`] asserts the
+/// diff of each expanded record exactly once however many copies of it there are.
+pub fn expand_frontiers<G, D, FromTime, R>(
+    updates: &Collection<G, ((D, FromTime), Vec<G::Timestamp>), R>,
+) -> Collection<G, Expanded<D, FromTime, R, G::Timestamp>, R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    R: Abelian + ExchangeData,
+{
+    updates
+        .consolidate()
+        .inner
+        .flat_map(|(((data, from_ts), frontier), time, diff)| {
+            // The update is reclocked into the join of its time with each element of its frontier,
+            // so that neither its copies nor their retraction are at times before it
+            let joined = Antichain::from_iter(frontier.iter().map(|t| t.join(&time)));
+            let record = ((data, frontier, time.clone()), from_ts, diff);
+            reclock_record(record, joined)
+                .into_iter()
+                .map(move |(record, into_ts, diff)| ((record, into_ts), time.clone(), diff))
+        })
+        .as_collection()
+}
+
+/// Reclocks expanded source updates, asserting their original diff at every time they have
+/// copies at. The neu updates are only needed for the intermediate work and are dropped from the
+/// result.
+///
+/// The `AltNeu` time of every expanded update must be beyond the time of the update itself, as
+/// the updates of [`expand_frontiers`] are. A `Neu` time that is not would be joined into an `Alt`
+/// time and cancel out the copies of its record there.
+///
+/// The arrangement of the expanded updates is compacted up to the frontier of its input as it
+/// advances. The updates of every record sum to zero, so once `IntoTime` has moved beyond the join
+/// of a frontier they are compacted to a single time and cancel out, and the arrangement does not
+/// grow with the number of records reclocked.
+pub fn reclock_expanded<G, D, FromTime, R>(
+    expanded: &Collection<G, Expanded<D, FromTime, R, G::Timestamp>, R>,
+) -> Collection<G, (D, FromTime), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    R: Abelian + ExchangeData + Hash,
+{
+    let mut scope = expanded.scope();
+    scope.scoped::<AltNeu<G::Timestamp>, _, _>("Reclock", |inner| {
+        let expanded = expanded
+            .enter(inner)
+            .inner
+            .map(|((record, into_ts), time, diff)| (record, into_ts.join(&time), diff))
+            .as_collection()
+            .arrange_by_self();
+        compact(&expanded);
+        expanded
+            .reduce(|(_data, _from_ts, diff), _input, output| {
+                // At any timestamp that this record has copies at we must re-assert that it has
+                // its original diff.
+                output.push(((), diff.clone()));
+            })
+            .integrate()
+            .map(|(((data, _frontier, _time), from_ts, _diff), ())| (data, from_ts))
+    })
+}
+
+/// Computes the updates that make a record with `diff` present at every time beyond `frontier`
+/// and absent everywhere else, without going through an `AltNeu` scope.
+///
+/// The updates are at the joins of the subsets of `frontier`. Visiting them so that every join
+/// comes after the joins below it, each one corrects the accumulated diff to `diff`, which is what
+/// the reduce of [`reclock_expanded`] ends up asserting. Joins that need no correction are left
+/// out.
+pub fn reclock_corrections<T, R>(frontier: &[T], diff: &R) -> Vec<(T, R)>
+where
+    T: Lattice + Clone + Eq,
+    R: Abelian,
+{
+    let mut corrections: Vec<(T, R)> = vec![];
+    for time in join_closure(frontier) {
+        let mut correction = diff.clone();
+        for (t, r) in corrections.iter() {
+            if t.less_equal(&time) {
+                correction.plus_equals(&r.clone().negate());
+            }
+        }
+        if !correction.is_zero() {
+            corrections.push((time, correction));
+        }
+    }
+    corrections
+}
+
+/// Computes the joins of every non-empty subset of `elements`, ordered so that every join comes
+/// after the joins below it.
+pub(crate) fn join_closure<T: Lattice + Clone + Eq>(elements: &[T]) -> Vec<T> {
+    let mut joins: Vec<T> = vec![];
+    for element in elements {
+        let mut new = vec![element.clone()];
+        new.extend(joins.iter().map(|join| join.join(element)));
+        for join in new {
+            if !joins.contains(&join) {
+                joins.push(join);
+            }
+        }
+    }
+
+    // A join is above fewer joins than any join it is below
+    let below = |time: &T| joins.iter().filter(|t| t.less_equal(time)).count();
+    let mut joins: Vec<(usize, T)> = joins.iter().map(|t| (below(t), t.clone())).collect();
+    joins.sort_by_key(|(below, _)| *below);
+    joins.into_iter().map(|(_, join)| join).collect()
+}
+
+/// The widest frontier whose subsets [`inclusion_exclusion`] enumerates.
+pub const INCLUSION_EXCLUSION_MAX_WIDTH: usize = 8;
+
+/// Computes the updates of [`reclock_corrections`] in closed form, for frontiers whose joins form
+/// a distributive lattice.
+///
+/// Every non-empty subset of `frontier` contributes `diff` at its join if it has an odd number of
+/// elements, and its negation otherwise. In a distributive lattice the terms that remain once
+/// they are consolidated are the Möbius function of the joins, scaled by `diff`.
+///
+/// Returns a counterexample to distributivity among the joins of `frontier` if there is one, in
+/// which case [`reclock_corrections`] should be used instead. The number of subsets and the cost
+/// of the check grow exponentially with the size of the frontier, so frontiers of more than
+/// [`INCLUSION_EXCLUSION_MAX_WIDTH`] elements are neither enumerated nor checked, and always fall
+/// back to the [`reclock_corrections`] of their join closure, consolidated in the same way.
+pub fn inclusion_exclusion<T, R>(frontier: &[T], diff: &R) -> Result<Vec<(T, R)>, Violation<T>>
+where
+    T: Lattice + Clone + Ord,
+    R: Abelian,
+{
+    if frontier.len() > INCLUSION_EXCLUSION_MAX_WIDTH {
+        let mut corrections = reclock_corrections(frontier, diff);
+        consolidate(&mut corrections);
+        return Ok(corrections);
+    }
+
+    laws::check_distributive(&join_closure(frontier))?;
+
+    let mut terms = vec![];
+    for subset in 1..1u32 << frontier.len() {
+        let mut join: Option<T> = None;
+        for (i, element) in frontier.iter().enumerate() {
+            if subset & (1 << i) != 0 {
+                join = Some(match join {
+                    Some(join) => join.join(element),
+                    None => element.clone(),
+                });
+            }
+        }
+        let join = join.expect("subset is not empty");
+        let term = if subset.count_ones() % 2 == 1 {
+            diff.clone()
+        } else {
+            diff.clone().negate()
+        };
+        terms.push((join, term));
+    }
+    consolidate(&mut terms);
+    Ok(terms)
+}
+
+/// Reclocks source updates whose `IntoTime` frontier has already been determined, like
+/// [`reclock_frontiers`], by emitting the [`reclock_corrections`] of every update directly.
+///
+/// This needs neither an arrangement nor a nested scope, and the output accumulates to the output
+/// of [`reclock_frontiers`] at every time.
+pub fn reclock_direct<G, D, FromTime, R>(
+    updates: &Collection<G, ((D, FromTime), Vec<G::Timestamp>), R>,
+) -> Collection<G, (D, FromTime), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: timely::Data,
+    FromTime: timely::Data,
+    R: Abelian,
+{
+    updates
+        .inner
+        .flat_map(|((update, frontier), time, diff)| {
+            let frontier: Vec<_> = frontier.iter().map(|t| t.join(&time)).collect();
+            reclock_corrections(&frontier, &diff)
+                .into_iter()
+                .map(move |(time, diff)| (update.clone(), time, diff))
+        })
+        .as_collection()
+}
+
+/// A [`Semigroup`] whose values are idempotent, i.e. adding any value to itself leaves it
+/// unchanged.
+///
+/// Only diffs that implement this trait can be reclocked with [`reclock_idempotent`], which rejects
+/// diffs that are not idempotent, such as counts:
+///
+/// ```compile_fail
+/// use differential_dataflow::lattice::Lattice;
+/// use differential_dataflow::Collection;
+/// use timely::dataflow::Scope;
+///
+/// fn reclock<G: Scope>(updates: &Collection<G, (((), u64), Vec<G::Timestamp>), i64>)
+/// where
+///     G::Timestamp: Lattice,
+/// {
+///     demo_reclock_reduce::reclock_idempotent(updates);
+/// }
+/// ```
+pub trait Idempotent: Semigroup + Eq {}
+
+/// Reclocks source updates with idempotent diffs, such as presence or maximum, whose `IntoTime`
+/// frontier has already been determined.
+///
+/// Adding an idempotent diff to itself leaves it unchanged, so an update is present with its
+/// original diff at every time beyond its frontier once it is made visible at each element of the
+/// frontier, and never needs to be corrected at their joins. This is what allows reclocking diffs
+/// that cannot be negated. Diffs that are not idempotent would be overcounted at the joins and must
+/// be reclocked with [`reclock_frontiers`] or [`reclock_direct`] instead. Debug builds check that
+/// every diff really is idempotent.
+pub fn reclock_idempotent<G, D, FromTime, R>(
+    updates: &Collection<G, ((D, FromTime), Vec<G::Timestamp>), R>,
+) -> Collection<G, (D, FromTime), R>
+where
+    G: Scope,
+    G::Timestamp: Lattice,
+    D: timely::Data,
+    FromTime: timely::Data,
+    R: Idempotent,
+{
+    updates
+        .inner
+        .flat_map(|((update, frontier), time, diff)| {
+            debug_assert!(
+                {
+                    let mut twice = diff.clone();
+                    twice.plus_equals(&diff);
+                    twice == diff
+                },
+                "diff {diff:?} is not idempotent"
+            );
+            frontier
+                .into_iter()
+                .map(move |into_ts| (update.clone(), into_ts.join(&time), diff.clone()))
+        })
+        .as_collection()
+}
@@ -0,0 +1,83 @@
+//! A durable log of the remap bindings of a source.
+//!
+//! The bindings are appended to a local file as JSON lines, in the format of the bindings of a
+//! [`crate::scenario::Scenario`], so that a source that is reclocked again after a restart assigns
+//! its records the same `IntoTime`s as before. [`crate::ingest`] holds its output back at the
+//! times of the bindings until the source has replayed the offsets they bind:
+//!
+//! ```json
+//! { "upper": { "0": 1 }, "into_ts": "B" }
+//! { "upper": { "0": 3, "1": 2 }, "into_ts": "E" }
+//! ```
+//!
+//! Every binding is synced to disk before it is used. A crash in the middle of appending a binding
+//! leaves an unterminated line behind, which is discarded when the log is opened again.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::scenario::Binding;
+
+/// An append-only file of remap bindings.
+pub struct RemapLog {
+    path: PathBuf,
+    file: File,
+    bindings: Vec<Binding>,
+}
+
+impl RemapLog {
+    /// Opens the log at `path`, creating it if it does not exist, and reads back its bindings.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let context = |err: std::io::Error| format!("{}: {err}", path.display());
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(context)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(context)?;
+
+        // Discard the binding of an append that was interrupted
+        let complete = contents.rfind('\n').map_or(0, |end| end + 1);
+        if complete < contents.len() {
+            file.set_len(complete as u64).map_err(context)?;
+        }
+        let bindings = contents[..complete]
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                serde_json::from_str(line)
+                    .map_err(|err| format!("{}:{}: {err}", path.display(), number + 1))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            file,
+            bindings,
+        })
+    }
+
+    /// The bindings of the log, in the order they were appended.
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Appends `binding` to the log and syncs it to disk, unless the log already contains it.
+    pub fn append(&mut self, binding: Binding) -> Result<(), String> {
+        if self.bindings.contains(&binding) {
+            return Ok(());
+        }
+        let mut line = serde_json::to_string(&binding).expect("failed to serialize binding");
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|()| self.file.sync_data())
+            .map_err(|err| format!("{}: {err}", self.path.display()))?;
+        self.bindings.push(binding);
+        Ok(())
+    }
+}
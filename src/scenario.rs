@@ -0,0 +1,259 @@
+//! Reclocking scenarios loaded from JSON files.
+//!
+//! A scenario lists source records together with the `IntoTime` frontier each of them was
+//! reclocked into, and optionally the Hasse diagram of the lattice those times belong to. When the
+//! lattice is omitted the lattice of [`crate::order::Time`] is used:
+//!
+//! ```json
+//! {
+//!     "records": [
+//!         { "data": "data", "from_ts": 0, "diff": 2, "frontier": ["B", "C", "D"] }
+//!     ]
+//! }
+//! ```
+//!
+//! Instead of listing the frontier of every record, a scenario can describe a partitioned source
+//! by its remap bindings, in which case the frontier of each record is derived from its partition
+//! and offset as described in [`crate::offsets`]:
+//!
+//! ```json
+//! {
+//!     "bindings": [
+//!         { "upper": { "0": 1 }, "into_ts": "B" },
+//!         { "upper": { "0": 1, "1": 1 }, "into_ts": "E" }
+//!     ],
+//!     "records": [
+//!         { "data": "data", "partition": 1, "from_ts": 0, "diff": 2 }
+//!     ]
+//! }
+//! ```
+//!
+//! Records can also be reclocked into product ordered pairs, as described in
+//! [`crate::product`], in which case the scenario has no lattice and its times are written as
+//! `(outer, inner)`:
+//!
+//! ```json
+//! {
+//!     "time": "product",
+//!     "records": [
+//!         { "data": "data", "from_ts": 0, "diff": 1, "frontier": ["(1, 3)", "(2, 2)", "(3, 1)"] }
+//!     ]
+//! }
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use timely::progress::{Antichain, Timestamp};
+
+use crate::finite::{FiniteLattice, FiniteTime};
+use crate::offsets::{self, Offset, PartitionId, SourceUpper};
+use crate::oracle::ReclockedRecord;
+use crate::product::{self, Pair};
+
+/// A set of source records to reclock and the lattice they are reclocked into.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default, skip_serializing_if = "TimeDomain::is_finite")]
+    pub time: TimeDomain,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice: Option<Hasse>,
+    /// The remap bindings of a partitioned source, which determine the frontiers of the records
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<Binding>,
+    pub records: Vec<Record>,
+}
+
+/// The kind of `IntoTime` the records of a scenario are reclocked into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeDomain {
+    /// The elements of the lattice of the scenario
+    #[default]
+    Finite,
+    /// Product ordered pairs of integers
+    Product,
+}
+
+impl TimeDomain {
+    fn is_finite(&self) -> bool {
+        *self == TimeDomain::Finite
+    }
+}
+
+/// The records of a scenario resolved against its time domain, together with the times at which
+/// their multiplicities should be checked.
+#[derive(Clone, Debug)]
+pub enum Resolved {
+    Finite {
+        elements: Vec<FiniteTime>,
+        records: Vec<ReclockedRecord<String, Offset, i64, FiniteTime>>,
+    },
+    Product {
+        elements: Vec<Pair>,
+        records: Vec<ReclockedRecord<String, Offset, i64, Pair>>,
+    },
+}
+
+/// The Hasse diagram of a finite lattice.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hasse {
+    pub elements: Vec<String>,
+    /// The `(lower, upper)` pairs of the covering relation
+    pub covers: Vec<(String, String)>,
+}
+
+/// A binding of a partitioned source upper to the name of the `IntoTime` it was reached at.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Binding {
+    pub upper: SourceUpper,
+    pub into_ts: String,
+}
+
+/// A source record and the names of the `IntoTime`s it was reclocked into.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Record {
+    pub data: String,
+    #[serde(default)]
+    pub partition: PartitionId,
+    pub from_ts: u64,
+    pub diff: i64,
+    /// The frontier of the record, which must be omitted when the scenario has bindings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frontier: Vec<String>,
+}
+
+impl Scenario {
+    /// Reads a scenario from a JSON file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        Self::parse(&contents).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// Parses a scenario from its JSON representation.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        serde_json::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    /// Builds the lattice of the scenario.
+    pub fn lattice(&self) -> Result<FiniteLattice, String> {
+        let default;
+        let hasse = match &self.lattice {
+            Some(hasse) => hasse,
+            None => {
+                default = Hasse::order_time();
+                &default
+            }
+        };
+        let elements: Vec<&str> = hasse.elements.iter().map(String::as_str).collect();
+        let covers: Vec<(&str, &str)> = hasse
+            .covers
+            .iter()
+            .map(|(lower, upper)| (lower.as_str(), upper.as_str()))
+            .collect();
+        FiniteLattice::from_hasse(&elements, &covers)
+    }
+
+    /// Resolves the records of the scenario against its time domain.
+    pub fn resolve(&self) -> Result<Resolved, String> {
+        match self.time {
+            TimeDomain::Finite => {
+                let lattice = self.lattice()?;
+                let records = self.records(&lattice)?;
+                let elements = lattice.elements();
+                Ok(Resolved::Finite { elements, records })
+            }
+            TimeDomain::Product => {
+                if self.lattice.is_some() {
+                    return Err("a scenario of product times cannot have a lattice".to_owned());
+                }
+                let records = self.resolve_records(product::parse)?;
+                let elements = product::grid(records.iter().flat_map(|(_, f)| f.iter()));
+                Ok(Resolved::Product { elements, records })
+            }
+        }
+    }
+
+    /// Resolves the records of the scenario against the elements of `lattice`, deriving their
+    /// frontiers from the bindings if the scenario has any.
+    pub fn records(
+        &self,
+        lattice: &FiniteLattice,
+    ) -> Result<Vec<ReclockedRecord<String, Offset, i64, FiniteTime>>, String> {
+        self.resolve_records(|name| {
+            lattice
+                .element(name)
+                .ok_or_else(|| format!("unknown lattice element {name}"))
+        })
+    }
+
+    /// Resolves the records of the scenario, looking up the times they name with `element`.
+    fn resolve_records<T, F>(
+        &self,
+        element: F,
+    ) -> Result<Vec<ReclockedRecord<String, Offset, i64, T>>, String>
+    where
+        T: Timestamp,
+        F: Fn(&str) -> Result<T, String>,
+    {
+        let bindings = self
+            .bindings
+            .iter()
+            .map(|binding| Ok((binding.upper.clone(), element(&binding.into_ts)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        self.records
+            .iter()
+            .map(|record| {
+                let offset = (record.partition, record.from_ts);
+                let frontier = if bindings.is_empty() {
+                    record
+                        .frontier
+                        .iter()
+                        .map(|name| element(name))
+                        .collect::<Result<Antichain<_>, _>>()?
+                } else if record.frontier.is_empty() {
+                    offsets::frontier(&bindings, &offset)
+                } else {
+                    return Err(format!(
+                        "record {} lists a frontier but the scenario derives it from bindings",
+                        record.data
+                    ));
+                };
+                let update = (record.data.clone(), offset, record.diff);
+                Ok((update, frontier))
+            })
+            .collect()
+    }
+}
+
+impl Hasse {
+    /// The Hasse diagram of [`crate::order::Time`].
+    pub fn order_time() -> Self {
+        let edges = [
+            ("A", "B"),
+            ("A", "C"),
+            ("A", "D"),
+            ("B", "E"),
+            ("C", "E"),
+            ("C", "F"),
+            ("D", "F"),
+            ("E", "G"),
+            ("F", "G"),
+        ];
+        Self {
+            elements: ["A", "B", "C", "D", "E", "F", "G"]
+                .map(String::from)
+                .to_vec(),
+            covers: edges
+                .iter()
+                .map(|(lower, upper)| (lower.to_string(), upper.to_string()))
+                .collect(),
+        }
+    }
+}
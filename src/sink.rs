@@ -0,0 +1,98 @@
+//! Writes updates out as JSON lines, consolidated per completed timestamp.
+//!
+//! Updates of a timestamp are held back until the input frontier has moved past it, so that the
+//! lines of a timestamp are written out together, in a deterministic order, and only once they can
+//! no longer change. Timestamps are also written out in the order of `Ord`, which the timestamps of
+//! this crate extend their partial order with, so a timestamp waits for the incomparable ones
+//! before it that are still open. This makes the output of different runs directly comparable,
+//! however their progress was reported.
+
+use std::io::Write;
+
+use differential_dataflow::consolidation::consolidate;
+use differential_dataflow::difference::Semigroup;
+use serde::{Deserialize, Serialize};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::Operator;
+use timely::dataflow::{Scope, Stream};
+
+/// A single update of a reclocking dataflow, in the shape it is written out.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Line<D, FromTime, T, R> {
+    pub kind: Kind,
+    pub data: D,
+    pub from_ts: FromTime,
+    pub into_ts: T,
+    /// Whether an original update applies at the `Alt` or `Neu` copy of `into_ts`. Reclocked
+    /// updates live in the plain `IntoTime` domain and have no side.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    pub diff: R,
+}
+
+/// The stage of the dataflow an update was observed at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// A source update as generated by `reclock_record`
+    Original,
+    /// An update of the reclocked collection
+    Reclocked,
+}
+
+/// The copy of an `IntoTime` an `AltNeu` time refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Alt,
+    Neu,
+}
+
+/// Writes the updates of `stream` to `writer` as JSON lines, once their timestamp is complete.
+///
+/// The updates of each completed timestamp are consolidated and passed through `format` in order,
+/// and the resulting values are written one per line.
+pub fn json_lines<G, K, R, W, F, L>(
+    stream: &Stream<G, (K, G::Timestamp, R)>,
+    mut writer: W,
+    mut format: F,
+) where
+    G: Scope,
+    K: timely::Data + Ord,
+    R: Semigroup,
+    W: Write + 'static,
+    F: FnMut(K, &G::Timestamp, R) -> L + 'static,
+    L: Serialize,
+{
+    let mut buffer = Vec::new();
+    let mut pending: Vec<((G::Timestamp, K), R)> = Vec::new();
+    stream.sink(Pipeline, "JsonLines", move |input| {
+        input.for_each(|_cap, data| {
+            data.swap(&mut buffer);
+            pending.extend(
+                buffer
+                    .drain(..)
+                    .map(|(key, time, diff)| ((time, key), diff)),
+            );
+        });
+
+        let frontier = input.frontier().frontier();
+        let (mut complete, incomplete): (Vec<_>, Vec<_>) = std::mem::take(&mut pending)
+            .into_iter()
+            .partition(|((time, _), _)| {
+                !frontier.less_equal(time) && frontier.iter().all(|open| time < open)
+            });
+        pending = incomplete;
+
+        consolidate(&mut complete);
+        if complete.is_empty() {
+            return;
+        }
+        for ((time, key), diff) in complete {
+            let line =
+                serde_json::to_string(&format(key, &time, diff)).expect("failed to serialize line");
+            writeln!(writer, "{line}").expect("failed to write line");
+        }
+        writer.flush().expect("failed to flush lines");
+    });
+}
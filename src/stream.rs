@@ -0,0 +1,185 @@
+//! Drives the reclock dataflow as a long running service.
+//!
+//! A [`Reclocker`] owns the input of a reclock dataflow on one worker. Records arrive over time
+//! together with the frontier they were reclocked into, and the frontier of the source advances
+//! as the `IntoTime`s it reaches are closed. The input retains one capability for every element of
+//! the source frontier and downgrades them whenever the frontier advances, so the dataflow makes
+//! progress without the input ever being closed.
+//!
+//! Every worker owns a reclocker of its own and must advance it to the same frontiers. Between
+//! batches of records each worker is stepped until the reclocked output has caught up with the
+//! source, and the updates at the times that became complete are handed out.
+
+use std::cell::RefCell;
+use std::hash::Hash;
+use std::rc::Rc;
+
+use differential_dataflow::difference::Abelian;
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::{AsCollection, ExchangeData};
+use timely::communication::Allocate;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::unordered_input::{UnorderedHandle, UnorderedInput};
+use timely::dataflow::operators::{ActivateCapability, Inspect, Probe};
+use timely::order::PartialOrder;
+use timely::progress::{Antichain, Timestamp};
+use timely::worker::Worker;
+
+use crate::harness::{self, Pipeline, Updates};
+use crate::oracle::ReclockedRecord;
+use crate::reclock::{reclock_corrections, reclock_direct, reclock_frontiers};
+
+/// The source updates of a reclock dataflow, with the time they are introduced at.
+type Update<D, FromTime, T, R> = (((D, FromTime), Vec<T>), T, R);
+
+/// The input and output of a reclock dataflow running on one worker.
+pub struct Reclocker<D, FromTime, T, R>
+where
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    T: Timestamp + Lattice,
+    R: Abelian + ExchangeData + Hash,
+{
+    handle: UnorderedHandle<T, Update<D, FromTime, T, R>>,
+    /// One capability for every element of the source frontier
+    capabilities: Vec<ActivateCapability<T>>,
+    probe: Handle<T>,
+    /// The reclocked updates at times that are not complete yet
+    pending: Rc<RefCell<Vec<((T, (D, FromTime)), R)>>>,
+}
+
+impl<D, FromTime, T, R> Reclocker<D, FromTime, T, R>
+where
+    D: ExchangeData + Hash,
+    FromTime: ExchangeData + Hash,
+    T: Timestamp + Lattice,
+    R: Abelian + ExchangeData + Hash,
+{
+    /// Builds a reclock dataflow on `worker` using the given `pipeline`. The source frontier
+    /// starts out at the minimum `IntoTime`.
+    pub fn new<A: Allocate>(worker: &mut Worker<A>, pipeline: Pipeline) -> Self {
+        let mut probe = Handle::new();
+        let pending = Rc::new(RefCell::new(Vec::new()));
+
+        let (handle, capability) = worker.dataflow::<T, _, _>(|scope| {
+            let ((handle, capability), source) = scope.new_unordered_input();
+            let source = source.as_collection();
+            let reclocked = match pipeline {
+                Pipeline::Reduce => reclock_frontiers(&source),
+                Pipeline::Direct => reclock_direct(&source),
+            };
+            let pending = Rc::clone(&pending);
+            reclocked
+                .inner
+                .inspect(move |(update, time, diff)| {
+                    let time = time.clone();
+                    pending
+                        .borrow_mut()
+                        .push(((time, update.clone()), diff.clone()))
+                })
+                .probe_with(&mut probe);
+            (handle, capability)
+        });
+
+        Self {
+            handle,
+            capabilities: vec![capability],
+            probe,
+            pending,
+        }
+    }
+
+    /// The frontier of the source, which is empty once the reclocker is closed.
+    pub fn frontier(&self) -> Antichain<T> {
+        self.capabilities
+            .iter()
+            .map(|capability| capability.time().clone())
+            .collect()
+    }
+
+    /// Introduces `record`, which becomes visible at every time beyond its frontier.
+    ///
+    /// Every element of the frontier must be beyond the source frontier, but the frontier as a
+    /// whole need not be beyond any single element of it. The record is introduced once at every
+    /// join of its frontier elements that [`reclock_corrections`] needs, with the correction at
+    /// that join as its diff and using the capability the join is beyond. Each copy is reclocked
+    /// into the join it is introduced at, and together they accumulate to the record.
+    pub fn insert(&mut self, record: ReclockedRecord<D, FromTime, R, T>) -> Result<(), String> {
+        let ((data, from_ts, diff), frontier) = record;
+        let mut frontier = frontier.elements().to_vec();
+        frontier.sort();
+        // A record with an empty frontier is never visible, but it can only be introduced while
+        // the source is open
+        if frontier.is_empty() && self.capabilities.is_empty() {
+            return Err("the source is closed".to_owned());
+        }
+        for time in &frontier {
+            self.delayed(time)?;
+        }
+        for (time, diff) in reclock_corrections(&frontier, &diff) {
+            let capability = self.delayed(&time)?;
+            self.handle.session(capability).give((
+                ((data.clone(), from_ts.clone()), frontier.clone()),
+                time,
+                diff,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Advances the source frontier to `frontier`, downgrading the capabilities of the input.
+    ///
+    /// Every element of `frontier` must be beyond the current source frontier.
+    pub fn advance(&mut self, frontier: &Antichain<T>) -> Result<(), String> {
+        self.capabilities = frontier
+            .elements()
+            .iter()
+            .map(|time| self.delayed(time))
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    /// Closes the source, after which no more records can be introduced.
+    pub fn close(&mut self) {
+        self.capabilities.clear();
+    }
+
+    /// Whether the source is closed and all of its records have been reclocked.
+    pub fn done(&self) -> bool {
+        self.probe.done()
+    }
+
+    /// Steps `worker` until the reclocked output has caught up with the source frontier, and
+    /// returns the reclocked updates at the times that have become complete since the last step.
+    pub fn step<A: Allocate>(&mut self, worker: &mut Worker<A>) -> Updates<D, FromTime, T, R> {
+        let frontier = self.frontier();
+        while self
+            .probe
+            .with_frontier(|output| output.iter().any(|time| !frontier.less_equal(time)))
+        {
+            worker.step();
+        }
+
+        let (complete, pending) = self
+            .pending
+            .take()
+            .into_iter()
+            .partition(|((time, _), _)| !self.probe.less_equal(time));
+        *self.pending.borrow_mut() = pending;
+        harness::group_by_time(complete)
+    }
+
+    /// A capability for `time`, derived from the capabilities of the source frontier.
+    fn delayed(&self, time: &T) -> Result<ActivateCapability<T>, String> {
+        self.capabilities
+            .iter()
+            .find(|capability| capability.time().less_equal(time))
+            .map(|capability| capability.delayed(time))
+            .ok_or_else(|| {
+                format!(
+                    "{time:?} is not beyond the source frontier {:?}",
+                    self.frontier().elements()
+                )
+            })
+    }
+}
@@ -0,0 +1,65 @@
+//! Helpers shared by the integration tests, which not every test uses.
+#![allow(dead_code)]
+
+use differential_dataflow::lattice::Lattice;
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::finite::FiniteLattice;
+use demo_reclock_reduce::harness;
+use demo_reclock_reduce::oracle::{self, ReclockedRecord};
+use demo_reclock_reduce::order::Time;
+
+pub type Record = ReclockedRecord<String, u64, i64, Time>;
+
+/// Reclocks every batch of records at its time and returns the reclocked updates.
+pub fn reclock(batches: Vec<(Time, Vec<Record>)>) -> Vec<(String, Time, i64)> {
+    let updates = harness::reclock_batches(1, batches).unwrap();
+    updates
+        .into_iter()
+        .flat_map(|(time, updates)| {
+            updates
+                .into_iter()
+                .map(move |((data, _from_ts), diff)| (data, time, diff))
+        })
+        .collect()
+}
+
+/// Checks that the reclocked output of the batches accumulates to the reclocked input at every
+/// time. Records that arrive at a time are visible no earlier than that time.
+pub fn check(batches: Vec<(Time, Vec<Record>)>) {
+    let expected: Vec<Record> = batches
+        .iter()
+        .flat_map(|(time, records)| {
+            records.iter().map(move |(record, frontier)| {
+                let frontier = frontier.iter().map(|t| t.join(time)).collect();
+                (record.clone(), frontier)
+            })
+        })
+        .collect();
+    let output = reclock(batches);
+    if let Err(report) = oracle::verify(&expected, &output, &Time::ELEMENTS) {
+        panic!("{report}");
+    }
+}
+
+pub fn record(data: &str, from_ts: u64, diff: i64, frontier: &[Time]) -> Record {
+    let frontier = Antichain::from_iter(frontier.iter().copied());
+    ((data.to_owned(), from_ts, diff), frontier)
+}
+
+/// The lattice of divisors of 60 ordered by divisibility, which is distributive.
+pub fn divisors_of_60() -> FiniteLattice {
+    let divisors = [
+        "1", "2", "3", "4", "5", "6", "10", "12", "15", "20", "30", "60",
+    ];
+    let mut covers = vec![];
+    for a in divisors {
+        for b in divisors {
+            let (x, y): (u32, u32) = (a.parse().unwrap(), b.parse().unwrap());
+            if y % x == 0 && [2, 3, 5].contains(&(y / x)) {
+                covers.push((a, b));
+            }
+        }
+    }
+    FiniteLattice::from_hasse(&divisors, &covers).unwrap()
+}
@@ -0,0 +1,219 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use differential_dataflow::input::Input;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::{Inspect, Probe};
+use timely::order::Product;
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::harness;
+use demo_reclock_reduce::offsets::{Offset, SourceUpper};
+use demo_reclock_reduce::oracle::{self, ReclockedRecord};
+use demo_reclock_reduce::{reclock_frontiers, ReclockExt, ReclockOffsetsExt};
+
+const EPOCHS: u64 = 200;
+const RECORDS: u64 = 10;
+
+/// Streams records through the reclock dataflow one epoch at a time and returns the number of
+/// updates held in arrangements once every epoch has been reclocked.
+fn frontiers_trace_sizes() -> Vec<isize> {
+    timely::execute_directly(|worker| {
+        let size = harness::track_arrangements(worker);
+
+        let mut probe = Handle::new();
+        let mut input = worker.dataflow::<Product<u64, u64>, _, _>(|scope| {
+            let (input, source) = scope.new_collection::<((u64, u64), Vec<_>), i64>();
+            reclock_frontiers(&source).probe_with(&mut probe);
+            input
+        });
+
+        let mut sizes = Vec::new();
+        for epoch in 0..EPOCHS {
+            // Every record of the epoch is reclocked into a frontier of two incomparable times,
+            // whose join the epoch after the next one is beyond
+            let frontier = vec![
+                Product::new(epoch, epoch + 1),
+                Product::new(epoch + 1, epoch),
+            ];
+            for i in 0..RECORDS {
+                input.insert(((i, epoch), frontier.clone()));
+            }
+            input.advance_to(Product::new(epoch + 1, epoch + 1));
+            input.flush();
+            while probe.less_than(input.time()) {
+                worker.step();
+            }
+            worker.log_register().flush();
+            sizes.push(size.get().current);
+        }
+        sizes
+    })
+}
+
+/// The reclocked updates of a dataflow, as `(data, time, diff)` triples.
+type Output = Rc<RefCell<Vec<(u64, u64, i64)>>>;
+
+/// Streams records and the bindings that cover them through `reclock_offsets` one epoch at a time
+/// and returns the number of updates held in arrangements after every epoch, together with the
+/// reclocked output.
+///
+/// The source keeps the records of the last two epochs and the remap collection the bindings of
+/// the last three, so the live data stays the same from one epoch to the next.
+fn offsets_trace_sizes() -> (Vec<isize>, Vec<(u64, u64, i64)>) {
+    timely::execute_directly(|worker| {
+        let size = harness::track_arrangements(worker);
+
+        let mut probe = Handle::new();
+        let output: Output = Rc::default();
+        let (mut source, mut remap) = worker.dataflow::<u64, _, _>(|scope| {
+            let (source_input, source) = scope.new_collection::<(u64, Offset), i64>();
+            let (remap_input, remap) = scope.new_collection();
+            let output = Rc::clone(&output);
+            source
+                .reclock_offsets(&remap)
+                .inner
+                .inspect(move |update| output.borrow_mut().push(*update))
+                .probe_with(&mut probe);
+            (source_input, remap_input)
+        });
+
+        let binding = |epoch: u64| {
+            (
+                SourceUpper::from_iter([(0, (epoch + 1) * RECORDS)]),
+                epoch + 1,
+            )
+        };
+        let mut sizes = Vec::new();
+        for epoch in 0..EPOCHS {
+            for offset in epoch * RECORDS..(epoch + 1) * RECORDS {
+                source.insert((offset, (0, offset)));
+            }
+            remap.insert(binding(epoch));
+            if epoch >= 2 {
+                for offset in (epoch - 2) * RECORDS..(epoch - 1) * RECORDS {
+                    source.remove((offset, (0, offset)));
+                }
+            }
+            if epoch >= 3 {
+                remap.remove(binding(epoch - 3));
+            }
+            source.advance_to(epoch + 1);
+            remap.advance_to(epoch + 1);
+            source.flush();
+            remap.flush();
+            while probe.less_than(source.time()) {
+                worker.step();
+            }
+            worker.log_register().flush();
+            sizes.push(size.get().current);
+        }
+        (sizes, output.take())
+    })
+}
+
+/// Like [`offsets_trace_sizes`], but through `reclock_remap` with a single source upper.
+fn remap_trace_sizes() -> (Vec<isize>, Vec<(u64, u64, i64)>) {
+    timely::execute_directly(|worker| {
+        let size = harness::track_arrangements(worker);
+
+        let mut probe = Handle::new();
+        let output: Output = Rc::default();
+        let (mut source, mut remap) = worker.dataflow::<u64, _, _>(|scope| {
+            let (source_input, source) = scope.new_collection::<(u64, u64), i64>();
+            let (remap_input, remap) = scope.new_collection();
+            let output = Rc::clone(&output);
+            source
+                .reclock_remap(&remap, |_from_ts: &u64| ())
+                .inner
+                .inspect(move |update| output.borrow_mut().push(*update))
+                .probe_with(&mut probe);
+            (source_input, remap_input)
+        });
+
+        let binding = |epoch: u64| ((), (vec![(epoch + 1) * RECORDS], epoch + 1));
+        let mut sizes = Vec::new();
+        for epoch in 0..EPOCHS {
+            for offset in epoch * RECORDS..(epoch + 1) * RECORDS {
+                source.insert((offset, offset));
+            }
+            remap.insert(binding(epoch));
+            if epoch >= 2 {
+                for offset in (epoch - 2) * RECORDS..(epoch - 1) * RECORDS {
+                    source.remove((offset, offset));
+                }
+            }
+            if epoch >= 3 {
+                remap.remove(binding(epoch - 3));
+            }
+            source.advance_to(epoch + 1);
+            remap.advance_to(epoch + 1);
+            source.flush();
+            remap.flush();
+            while probe.less_than(source.time()) {
+                worker.step();
+            }
+            worker.log_register().flush();
+            sizes.push(size.get().current);
+        }
+        (sizes, output.take())
+    })
+}
+
+/// The records streamed by [`offsets_trace_sizes`] and [`remap_trace_sizes`], whose data is their
+/// offset. The records of every epoch are bound to the end of the epoch, and are retracted two
+/// epochs later from that same frontier.
+fn expected<F>(from_ts: impl Fn(u64) -> F) -> Vec<ReclockedRecord<u64, F, i64, u64>> {
+    let mut records = vec![];
+    for epoch in 0..EPOCHS {
+        for offset in epoch * RECORDS..(epoch + 1) * RECORDS {
+            let frontier = Antichain::from_elem(epoch + 1);
+            records.push(((offset, from_ts(offset), 1), frontier));
+            if epoch + 2 < EPOCHS {
+                // Arriving after its frontier, the retraction is reclocked into its own epoch
+                let frontier = Antichain::from_elem(epoch + 2);
+                records.push(((offset, from_ts(offset), -1), frontier));
+            }
+        }
+    }
+    records
+}
+
+/// Checks that `output` is the reclocked `records` at every epoch that is complete.
+fn assert_reclocked<F>(records: &[ReclockedRecord<u64, F, i64, u64>], output: &[(u64, u64, i64)]) {
+    let epochs: Vec<u64> = (0..EPOCHS).collect();
+    if let Err(report) = oracle::verify(records, output, &epochs) {
+        panic!("{report}");
+    }
+}
+
+/// Checks that once the dataflow has warmed up the arrangements never hold more updates than they
+/// did during the first epochs after it. Without compaction every epoch would add its updates to
+/// the arrangements for good.
+fn assert_plateau(sizes: &[isize]) {
+    let early = *sizes[20..100].iter().max().unwrap();
+    let late = *sizes[100..].iter().max().unwrap();
+    assert!(
+        late <= early,
+        "arrangements grew from {early} to {late} updates"
+    );
+}
+
+#[test]
+fn trace_size_plateaus() {
+    assert_plateau(&frontiers_trace_sizes());
+}
+
+#[test]
+fn offsets_trace_size_plateaus() {
+    let (sizes, output) = offsets_trace_sizes();
+    assert_reclocked(&expected(|offset| (0, offset)), &output);
+    assert_plateau(&sizes);
+}
+
+#[test]
+fn remap_trace_size_plateaus() {
+    let (sizes, output) = remap_trace_sizes();
+    assert_reclocked(&expected(|offset| offset), &output);
+    assert_plateau(&sizes);
+}
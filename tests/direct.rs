@@ -0,0 +1,146 @@
+mod common;
+
+use differential_dataflow::consolidation::consolidate;
+use timely::order::Product;
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::finite::FiniteLattice;
+use demo_reclock_reduce::harness::{self, Pipeline};
+use demo_reclock_reduce::laws::Law;
+use demo_reclock_reduce::order::Time;
+use demo_reclock_reduce::{inclusion_exclusion, reclock_corrections};
+
+use common::{divisors_of_60, record, Record};
+
+/// Checks that both pipelines reclock the batches correctly and into the same updates.
+fn assert_equivalent(batches: Vec<(Time, Vec<Record>)>) {
+    common::check(batches.clone());
+    let reduce = harness::reclock_with(Pipeline::Reduce, 1, batches.clone()).unwrap();
+    let direct = harness::reclock_with(Pipeline::Direct, 1, batches.clone()).unwrap();
+    assert_eq!(direct, reduce);
+    let direct = harness::reclock_with(Pipeline::Direct, 3, batches).unwrap();
+    assert_eq!(direct, reduce);
+}
+
+#[test]
+fn corrections_of_the_demo_record() {
+    let corrections = reclock_corrections(&[Time::B, Time::C, Time::D], &2);
+    assert_eq!(
+        corrections,
+        [
+            (Time::B, 2),
+            (Time::C, 2),
+            (Time::D, 2),
+            (Time::E, -2),
+            (Time::F, -2),
+        ]
+    );
+}
+
+#[test]
+fn corrections_of_trivial_frontiers() {
+    assert_eq!(reclock_corrections::<Time, i64>(&[], &1), []);
+    assert_eq!(reclock_corrections(&[Time::E], &-1), [(Time::E, -1)]);
+    assert_eq!(
+        reclock_corrections(&[Time::E, Time::F], &1),
+        [(Time::E, 1), (Time::F, 1), (Time::G, -1)]
+    );
+}
+
+#[test]
+fn direct_pipeline_matches_reduce_pipeline() {
+    let frontiers: [&[Time]; 5] = [
+        &[Time::B, Time::C, Time::D],
+        &[Time::B, Time::D],
+        &[Time::E, Time::F],
+        &[Time::A],
+        &[],
+    ];
+    let records: Vec<Record> = frontiers
+        .iter()
+        .enumerate()
+        .map(|(i, frontier)| record("data", i as u64, i as i64 - 2, frontier))
+        .collect();
+    assert_equivalent(vec![(Time::A, records.clone())]);
+    // At C the records into [A] and [B, D] arrive beyond or beside their frontiers
+    assert_equivalent(vec![(Time::A, records.clone()), (Time::C, records)]);
+}
+
+#[test]
+fn direct_pipeline_matches_reduce_pipeline_on_updates() {
+    let update = record("data", 0, 1, &[Time::B, Time::D]);
+    assert_equivalent(vec![
+        (Time::A, vec![update.clone(), update.clone()]),
+        (
+            Time::C,
+            vec![
+                record("data", 0, -1, &[Time::B, Time::D]),
+                record("data", 0, 2, &[Time::E, Time::F]),
+            ],
+        ),
+    ]);
+    // A record into [C] that arrives at B is reclocked into their join
+    let frontier = Antichain::from_iter([Time::C]);
+    assert_equivalent(vec![(
+        Time::B,
+        vec![(("other".to_owned(), 1, 3), frontier)],
+    )]);
+}
+
+#[test]
+fn inclusion_exclusion_matches_corrections_in_distributive_lattices() {
+    let elements = divisors_of_60().elements();
+    for a in &elements {
+        for b in &elements {
+            for c in &elements {
+                let frontier = Antichain::from_iter([a.clone(), b.clone(), c.clone()]);
+                let mut corrections = reclock_corrections(frontier.elements(), &3);
+                corrections.sort();
+                let closed_form = inclusion_exclusion(frontier.elements(), &3).unwrap();
+                assert_eq!(closed_form, corrections, "{frontier:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn inclusion_exclusion_falls_back_for_wide_frontiers() {
+    // A staircase of product ordered pairs, which is too wide to enumerate its subsets
+    let frontier: Vec<Product<u64, u64>> = (0..40).map(|i| Product::new(i, 40 - i)).collect();
+    let mut corrections = reclock_corrections(&frontier, &1);
+    consolidate(&mut corrections);
+    assert_eq!(inclusion_exclusion(&frontier, &1).unwrap(), corrections);
+}
+
+#[test]
+fn inclusion_exclusion_refuses_non_distributive_joins() {
+    let closed_form = inclusion_exclusion(&[Time::B, Time::C], &1).unwrap();
+    assert_eq!(closed_form, [(Time::B, 1), (Time::C, 1), (Time::E, -1)]);
+
+    let violation = inclusion_exclusion(&[Time::B, Time::C, Time::D], &2).unwrap_err();
+    assert_eq!(violation.law, Law::Distributivity);
+}
+
+#[test]
+fn inclusion_exclusion_only_checks_the_frontiers_it_enumerates() {
+    // Nine atoms between a bottom and a top, which are not distributive
+    let atoms: Vec<String> = (0..9).map(|i| format!("atom{i}")).collect();
+    let mut elements = vec!["bottom", "top"];
+    elements.extend(atoms.iter().map(String::as_str));
+    let covers: Vec<(&str, &str)> = atoms
+        .iter()
+        .flat_map(|atom| [("bottom", atom.as_str()), (atom.as_str(), "top")])
+        .collect();
+    let lattice = FiniteLattice::from_hasse(&elements, &covers).unwrap();
+    let atoms: Vec<_> = atoms
+        .iter()
+        .map(|atom| lattice.element(atom).unwrap())
+        .collect();
+
+    let violation = inclusion_exclusion(&atoms[..3], &1).unwrap_err();
+    assert_eq!(violation.law, Law::Distributivity);
+
+    let mut corrections = reclock_corrections(&atoms, &1);
+    consolidate(&mut corrections);
+    assert_eq!(inclusion_exclusion(&atoms, &1).unwrap(), corrections);
+}
@@ -0,0 +1,43 @@
+use demo_reclock_reduce::dot::hasse_diagram;
+use demo_reclock_reduce::order::Time;
+
+#[test]
+fn renders_the_hasse_diagram_of_order_time() {
+    let diagram = hasse_diagram(&Time::ELEMENTS, &[Time::B, Time::C], |time| match time {
+        Time::B => vec!["\"data\": 2".to_owned()],
+        _ => vec![],
+    });
+    let expected = r##"digraph lattice {
+    rankdir=BT;
+    node [shape=box];
+    n0 [label="A"];
+    n1 [label="B\n\"data\": 2", style=filled, fillcolor="#ffe08a"];
+    n2 [label="C", style=filled, fillcolor="#ffe08a"];
+    n3 [label="D"];
+    n4 [label="E"];
+    n5 [label="F"];
+    n6 [label="G"];
+    n0 -> n1;
+    n0 -> n2;
+    n0 -> n3;
+    n1 -> n4;
+    n2 -> n4;
+    n2 -> n5;
+    n3 -> n5;
+    n4 -> n6;
+    n5 -> n6;
+}
+"##;
+    assert_eq!(diagram, expected);
+}
+
+#[test]
+fn escapes_only_quotes_and_backslashes() {
+    let diagram = hasse_diagram(&[Time::A], &[], |_| {
+        vec![r"C:\data".to_owned(), "\"größe\": 2".to_owned()]
+    });
+    assert!(
+        diagram.contains(r#"n0 [label="A\nC:\\data\n\"größe\": 2"];"#),
+        "{diagram}"
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use demo_reclock_reduce::oracle;
+use demo_reclock_reduce::order::Time;
+
+use common::{check, reclock, record};
+
+#[test]
+fn identical_updates_at_the_same_time() {
+    let update = record("data", 0, 2, &[Time::B, Time::C, Time::D]);
+    check(vec![(
+        Time::A,
+        vec![update.clone(), update.clone(), update],
+    )]);
+
+    let update = record("data", 0, 1, &[Time::B, Time::D]);
+    let output = reclock(vec![(Time::A, vec![update.clone(), update])]);
+    assert_eq!(
+        oracle::accumulate_at(&output, &Time::B),
+        [("data".to_owned(), 2)]
+    );
+    assert_eq!(
+        oracle::accumulate_at(&output, &Time::G),
+        [("data".to_owned(), 2)]
+    );
+}
+
+#[test]
+fn identical_updates_at_different_times() {
+    let update = record("data", 0, 1, &[Time::B, Time::D]);
+    check(vec![
+        (Time::A, vec![update.clone()]),
+        (Time::C, vec![update]),
+    ]);
+}
+
+#[test]
+fn identical_updates_and_their_retraction() {
+    let update = record("data", 0, 1, &[Time::B, Time::C]);
+    let retraction = record("data", 0, -1, &[Time::B, Time::C]);
+    check(vec![
+        (
+            Time::A,
+            vec![update.clone(), update.clone(), retraction.clone()],
+        ),
+        (Time::C, vec![update, retraction]),
+    ]);
+}
@@ -0,0 +1,58 @@
+use dogsdogsdogs::altneu::AltNeu;
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::explain::explain;
+use demo_reclock_reduce::order::Time;
+
+#[test]
+fn explains_the_demo_record() {
+    let frontier = Antichain::from_iter([Time::B, Time::C, Time::D]);
+    let explanation = explain(("data", 0, 2), frontier, &Time::ELEMENTS);
+
+    let (alt, neu) = (AltNeu::alt, AltNeu::neu);
+    assert_eq!(
+        explanation.expanded,
+        [
+            (alt(Time::B), 2),
+            (alt(Time::C), 2),
+            (alt(Time::D), 2),
+            (neu(Time::G), -6),
+        ]
+    );
+    assert_eq!(
+        explanation.reduced,
+        [
+            (alt(Time::B), 2),
+            (alt(Time::C), 2),
+            (alt(Time::D), 2),
+            (alt(Time::E), -2),
+            (alt(Time::F), -2),
+            (neu(Time::G), -2),
+        ]
+    );
+    let multiplicities: Vec<_> = Time::ELEMENTS
+        .iter()
+        .map(|&time| (time, if time == Time::A { 0 } else { 2 }))
+        .collect();
+    assert_eq!(explanation.multiplicities, multiplicities);
+
+    let table = explanation.to_string();
+    let lines: Vec<_> = table.lines().collect();
+    assert_eq!(lines[0], "    time    reclock_record  reduce");
+    assert_eq!(lines[1], "    Alt(B)  2               2");
+    assert_eq!(lines[4], "    Alt(E)                  -2");
+    assert_eq!(lines[6], "    Neu(G)  -6              -2");
+    assert_eq!(lines[8], "    element  multiplicity");
+    assert_eq!(lines[9], "    A        0");
+}
+
+#[test]
+fn explains_records_without_frontier() {
+    let explanation = explain(("data", 0, 1), Antichain::new(), &Time::ELEMENTS);
+    assert_eq!(explanation.expanded, []);
+    assert_eq!(explanation.reduced, []);
+    assert!(explanation
+        .multiplicities
+        .iter()
+        .all(|(_, diff)| *diff == 0));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use timely::progress::Timestamp;
+
+use demo_reclock_reduce::finite::{FiniteLattice, FiniteTime};
+
+/// Builds a lattice and returns its error.
+fn error(elements: &[&str], covers: &[(&str, &str)]) -> String {
+    FiniteLattice::from_hasse(elements, covers).unwrap_err()
+}
+
+#[test]
+fn rejects_invalid_hasse_diagrams() {
+    assert_eq!(error(&[], &[]), "a lattice must have at least one element");
+    assert_eq!(error(&["a", "b", "a"], &[]), "duplicate element a");
+    assert_eq!(error(&["a", "b"], &[("a", "z")]), "unknown element z");
+    assert_eq!(
+        error(&["a", "b", "c"], &[("a", "b"), ("b", "c"), ("c", "b")]),
+        "c and b form a cycle"
+    );
+}
+
+#[test]
+fn rejects_missing_bounds() {
+    // Two incomparable elements have neither a join nor a meet
+    assert!(error(&["a", "b"], &[]).contains("have no join"));
+    // Adding a top gives them a join, but still no meet
+    assert_eq!(
+        error(&["a", "b", "top"], &[("a", "top"), ("b", "top")]),
+        "a and b have no meet"
+    );
+    // Two minimal upper bounds are not a join
+    assert_eq!(
+        error(
+            &["bottom", "a", "b", "c", "d"],
+            &[
+                ("bottom", "a"),
+                ("bottom", "b"),
+                ("a", "c"),
+                ("a", "d"),
+                ("b", "c"),
+                ("b", "d"),
+            ],
+        ),
+        "a and b have no join"
+    );
+}
+
+#[test]
+fn elements_serialize_without_their_lattice() {
+    let lattice = common::divisors_of_60();
+    for element in lattice.elements() {
+        let encoded = serde_json::to_string(&element).unwrap();
+        assert!(encoded.len() < 64, "{encoded}");
+        let decoded: FiniteTime = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(decoded, element);
+        assert_eq!(decoded.name(), element.name());
+    }
+
+    let minimum = serde_json::to_string(&FiniteTime::minimum()).unwrap();
+    let decoded: FiniteTime = serde_json::from_str(&minimum).unwrap();
+    assert_eq!(decoded.name(), None);
+}
+
+#[test]
+fn elements_of_unknown_lattices_are_rejected() {
+    let unknown = r#"{ "index": 1, "lattice": 0 }"#;
+    assert!(serde_json::from_str::<FiniteTime>(unknown).is_err());
+    let orphan = r#"{ "index": 1, "lattice": null }"#;
+    assert!(serde_json::from_str::<FiniteTime>(orphan).is_err());
+}
+
+#[test]
+fn dropped_lattices_are_forgotten() {
+    let lattice =
+        FiniteLattice::from_hasse(&["forgotten", "top"], &[("forgotten", "top")]).unwrap();
+    let encoded = serde_json::to_string(&lattice.element("top").unwrap()).unwrap();
+    assert!(serde_json::from_str::<FiniteTime>(&encoded).is_ok());
+
+    drop(lattice);
+    assert!(serde_json::from_str::<FiniteTime>(&encoded).is_err());
+}
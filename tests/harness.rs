@@ -0,0 +1,58 @@
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::finite::FiniteLattice;
+use demo_reclock_reduce::harness;
+use demo_reclock_reduce::order::Time;
+
+#[test]
+fn demo_record_is_corrected_at_the_joins() {
+    let frontier = Antichain::from_iter([Time::B, Time::C, Time::D]);
+    let records = vec![(("data".to_owned(), 0u64, 2), frontier)];
+    let updates = harness::reclock(1, records).unwrap();
+    let data = |diff| vec![(("data".to_owned(), 0), diff)];
+    assert_eq!(
+        updates,
+        [
+            (Time::B, data(2)),
+            (Time::C, data(2)),
+            (Time::D, data(2)),
+            (Time::E, data(-2)),
+            (Time::F, data(-2)),
+        ]
+    );
+}
+
+#[test]
+fn output_does_not_depend_on_the_number_of_workers() {
+    let lattice = FiniteLattice::from_hasse(
+        &["bottom", "left", "right", "top"],
+        &[
+            ("bottom", "left"),
+            ("bottom", "right"),
+            ("left", "top"),
+            ("right", "top"),
+        ],
+    )
+    .unwrap();
+    let element = |name| lattice.element(name).unwrap();
+    let records: Vec<_> = (0..20u64)
+        .map(|i| {
+            let frontier = match i % 3 {
+                0 => vec![element("left"), element("right")],
+                1 => vec![element("left")],
+                _ => vec![element("bottom")],
+            };
+            let diff = if i % 4 == 0 { -1 } else { 1 };
+            ((i % 5, i, diff), Antichain::from_iter(frontier))
+        })
+        .collect();
+
+    let expected = harness::reclock(1, records.clone()).unwrap();
+    assert!(!expected.is_empty());
+    for workers in [2, 4] {
+        assert_eq!(
+            harness::reclock(workers, records.clone()).unwrap(),
+            expected
+        );
+    }
+}
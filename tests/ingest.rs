@@ -0,0 +1,171 @@
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+
+use demo_reclock_reduce::finite::{FiniteLattice, FiniteTime};
+use demo_reclock_reduce::ingest::{self, Ingest};
+use demo_reclock_reduce::scenario::Scenario;
+
+const BIN: &str = env!("CARGO_BIN_EXE_demo-reclock-reduce");
+
+/// The lines of the demo scenario, as they would arrive from a source.
+const DEMO: &str = r#"{ "advance": ["B", "C", "D"] }
+{ "data": "data", "from_ts": 0, "diff": 2 }
+{ "advance": ["G"] }
+"#;
+
+fn order_time() -> FiniteLattice {
+    let scenario = Scenario::parse(r#"{ "records": [] }"#).unwrap();
+    scenario.lattice().unwrap()
+}
+
+#[test]
+fn updates_are_released_as_the_source_advances() {
+    let lattice = order_time();
+    let element = move |name: &str| -> Result<FiniteTime, String> {
+        lattice.element(name).ok_or_else(|| name.to_owned())
+    };
+    let time = element.clone();
+
+    let released = timely::execute_directly(move |worker| {
+        let mut ingest = Ingest::new(worker, element);
+        let mut released = vec![];
+        for line in DEMO.lines() {
+            released.push(ingest.line(worker, line).unwrap());
+        }
+        released.push(ingest.finish(worker));
+        released
+    });
+
+    let data = |diff| vec![(("data".to_owned(), (0, 0)), diff)];
+    assert_eq!(
+        released,
+        [
+            vec![],
+            vec![],
+            vec![
+                (time("B").unwrap(), data(2)),
+                (time("C").unwrap(), data(2)),
+                (time("D").unwrap(), data(2)),
+                (time("E").unwrap(), data(-2)),
+                (time("F").unwrap(), data(-2)),
+            ],
+            vec![],
+        ]
+    );
+}
+
+#[test]
+fn times_behind_the_source_are_rejected() {
+    let lattice = order_time();
+    timely::execute_directly(move |worker| {
+        let mut ingest = Ingest::new(worker, move |name: &str| {
+            lattice.element(name).ok_or_else(|| name.to_owned())
+        });
+        ingest.line(worker, r#"{ "advance": ["E", "F"] }"#).unwrap();
+
+        let record = r#"{ "data": "data", "from_ts": 0, "diff": 1, "frontier": ["C"] }"#;
+        assert!(ingest.line(worker, record).is_err());
+        assert!(ingest.line(worker, r#"{ "advance": ["D"] }"#).is_err());
+        assert!(ingest.line(worker, r#"{ "advance": ["X"] }"#).is_err());
+        assert!(ingest.line(worker, "not json").is_err());
+        assert_eq!(ingest.frontier().elements().len(), 2);
+
+        let record = r#"{ "data": "data", "from_ts": 0, "diff": 1, "frontier": ["G"] }"#;
+        ingest.line(worker, record).unwrap();
+        ingest.line(worker, r#"{ "advance": [] }"#).unwrap();
+        assert!(ingest.line(worker, record).is_err());
+    });
+}
+
+#[test]
+fn malformed_records_are_rejected() {
+    let lattice = order_time();
+    let released = timely::execute_directly(move |worker| {
+        let mut ingest = Ingest::new(worker, move |name: &str| {
+            lattice.element(name).ok_or_else(|| name.to_owned())
+        });
+        ingest.line(worker, r#"{ "advance": ["G"] }"#).unwrap();
+
+        let misspelled = r#"{ "data": "data", "from_ts": 0, "diff": 1, "fronteir": ["G"] }"#;
+        let err = ingest.line(worker, misspelled).unwrap_err();
+        assert!(err.contains("fronteir"), "{err}");
+
+        let last = format!(
+            r#"{{ "data": "data", "from_ts": {}, "diff": 1 }}"#,
+            u64::MAX
+        );
+        let err = ingest.line(worker, &last).unwrap_err();
+        assert_eq!(err, format!("offset {} is too large", u64::MAX));
+
+        ingest.finish(worker)
+    });
+    // Neither record was introduced
+    assert!(released.is_empty());
+}
+
+#[test]
+fn offsets_must_increase() {
+    let lattice = order_time();
+    timely::execute_directly(move |worker| {
+        let mut ingest = Ingest::new(worker, move |name: &str| {
+            lattice.element(name).ok_or_else(|| name.to_owned())
+        });
+        ingest.line(worker, r#"{ "advance": ["B"] }"#).unwrap();
+        let record = |partition: u32, from_ts: u64| {
+            format!(
+                r#"{{ "data": "data", "partition": {partition}, "from_ts": {from_ts}, "diff": 1 }}"#
+            )
+        };
+        ingest.line(worker, &record(0, 5)).unwrap();
+        ingest.line(worker, &record(1, 0)).unwrap();
+        ingest.line(worker, r#"{ "advance": ["E"] }"#).unwrap();
+
+        let err = ingest.line(worker, &record(0, 2)).unwrap_err();
+        assert_eq!(
+            err,
+            "offset 2 of partition 0 is behind the partition, which is at offset 6"
+        );
+        let err = ingest.line(worker, &record(0, 5)).unwrap_err();
+        assert_eq!(
+            err,
+            "offset 5 of partition 0 is behind the partition, which is at offset 6"
+        );
+        ingest.line(worker, &record(0, 6)).unwrap();
+        ingest.line(worker, &record(1, 1)).unwrap();
+    });
+}
+
+#[test]
+fn lines_end_with_the_input() {
+    let input = Cursor::new("first\r\n\nsecond\nunterminated");
+    let lines: Vec<_> = ingest::lines(input, false)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(lines, ["first", "", "second", "unterminated"]);
+}
+
+#[test]
+fn binary_reclocks_stdin() {
+    let mut child = Command::new(BIN)
+        .arg("ingest")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(DEMO.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "reclocked record (\"data\", B, 2)\n\
+         reclocked record (\"data\", C, 2)\n\
+         reclocked record (\"data\", D, 2)\n\
+         reclocked record (\"data\", E, -2)\n\
+         reclocked record (\"data\", F, -2)\n"
+    );
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use differential_dataflow::lattice::Lattice;
+use timely::order::PartialOrder;
+
+use demo_reclock_reduce::laws::{self, Law};
+use demo_reclock_reduce::order::Time;
+
+#[test]
+fn order_time_is_a_lattice() {
+    if let Err(violation) = laws::check_exhaustive(&Time::ELEMENTS) {
+        panic!("{violation}");
+    }
+}
+
+#[test]
+fn finite_lattice_is_a_lattice() {
+    let lattice = common::divisors_of_60();
+    if let Err(violation) = laws::check_exhaustive(&lattice.elements()) {
+        panic!("{violation}");
+    }
+}
+
+#[test]
+fn distributivity() {
+    let lattice = common::divisors_of_60();
+    assert_eq!(laws::check_distributive(&lattice.elements()), Ok(()));
+
+    let violation = laws::check_distributive(&Time::ELEMENTS).unwrap_err();
+    assert_eq!(violation.law, Law::Distributivity);
+}
+
+#[test]
+fn sampled_integers_are_a_lattice() {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let sample = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % 1000
+    };
+    assert_eq!(laws::check_sampled(sample, 10_000), Ok(()));
+}
+
+/// A "lattice" over the integers whose join is really a meet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Upside(u64);
+
+impl PartialOrder for Upside {
+    fn less_equal(&self, other: &Self) -> bool {
+        self.0 <= other.0
+    }
+}
+
+impl Lattice for Upside {
+    fn join(&self, other: &Self) -> Self {
+        Upside(std::cmp::min(self.0, other.0))
+    }
+    fn meet(&self, other: &Self) -> Self {
+        Upside(std::cmp::min(self.0, other.0))
+    }
+}
+
+#[test]
+fn reports_first_counterexample() {
+    let violation = laws::check_exhaustive(&[Upside(0), Upside(1)]).unwrap_err();
+    assert_eq!(violation.law, Law::JoinUpperBound);
+    assert_eq!(violation.elements, vec![Upside(0), Upside(1)]);
+}
+
+/// A chain whose join forgot about one of its pairs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Partial(u64);
+
+impl PartialOrder for Partial {
+    fn less_equal(&self, other: &Self) -> bool {
+        self.0 <= other.0
+    }
+}
+
+impl Lattice for Partial {
+    fn join(&self, other: &Self) -> Self {
+        match (self.0, other.0) {
+            (0, 1) => unreachable!(),
+            (a, b) => Partial(std::cmp::max(a, b)),
+        }
+    }
+    fn meet(&self, other: &Self) -> Self {
+        Partial(std::cmp::min(self.0, other.0))
+    }
+}
+
+#[test]
+fn reports_panics_as_counterexamples() {
+    let violation = laws::check_exhaustive(&[Partial(0), Partial(1)]).unwrap_err();
+    assert_eq!(violation.law, Law::JoinDefined);
+    assert_eq!(violation.elements, vec![Partial(0), Partial(1)]);
+}
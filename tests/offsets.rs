@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use differential_dataflow::consolidation::consolidate;
+use differential_dataflow::input::Input;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::{Inspect, Probe};
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::offsets::{self, Offset, SourceUpper};
+use demo_reclock_reduce::oracle::{self, ReclockedRecord};
+use demo_reclock_reduce::order::Time;
+use demo_reclock_reduce::scenario::Scenario;
+use demo_reclock_reduce::ReclockOffsetsExt;
+
+fn bindings() -> Vec<(SourceUpper, Time)> {
+    vec![
+        (SourceUpper::from_iter([(0, 2)]), Time::B),
+        (SourceUpper::from_iter([(1, 1)]), Time::C),
+        (SourceUpper::from_iter([(0, 1), (1, 1)]), Time::D),
+        (SourceUpper::from_iter([(0, 2), (1, 1)]), Time::E),
+        (SourceUpper::from_iter([(0, 1), (1, 2)]), Time::F),
+        (SourceUpper::from_iter([(0, 3), (1, 2)]), Time::G),
+    ]
+}
+
+#[test]
+fn frontier_is_the_minimal_bindings_beyond_the_offset() {
+    let bindings = bindings();
+    let frontier = |offset: Offset| {
+        let mut frontier = offsets::frontier(&bindings, &offset).elements().to_vec();
+        frontier.sort();
+        frontier
+    };
+    assert_eq!(frontier((0, 0)), [Time::B, Time::D]);
+    assert_eq!(frontier((0, 1)), [Time::B]);
+    assert_eq!(frontier((1, 0)), [Time::C, Time::D]);
+    assert_eq!(frontier((1, 1)), [Time::F]);
+    assert_eq!(frontier((0, 2)), [Time::G]);
+    assert_eq!(frontier((0, 3)), []);
+    assert_eq!(frontier((2, 0)), []);
+}
+
+#[test]
+fn scenario_derives_frontiers_from_bindings() {
+    let scenario = Scenario::load("scenarios/offsets.json".as_ref()).unwrap();
+    let lattice = scenario.lattice().unwrap();
+    let records = scenario.records(&lattice).unwrap();
+    let frontiers: Vec<_> = records
+        .iter()
+        .map(|((data, _, _), frontier)| {
+            let names = frontier.iter().map(|t| t.name().unwrap_or("?"));
+            let mut names: Vec<_> = names.collect();
+            names.sort();
+            (data.as_str(), names)
+        })
+        .collect();
+    assert_eq!(
+        frontiers,
+        [
+            ("a", vec!["B", "D"]),
+            ("b", vec!["B"]),
+            ("c", vec!["C", "D"]),
+            ("d", vec!["F"]),
+            ("e", vec!["G"]),
+            ("f", vec![]),
+        ]
+    );
+}
+
+#[test]
+fn scenario_rejects_frontiers_next_to_bindings() {
+    let scenario = Scenario::parse(
+        r#"{
+            "bindings": [{ "upper": { "0": 1 }, "into_ts": "B" }],
+            "records": [{ "data": "a", "from_ts": 0, "diff": 1, "frontier": ["C"] }]
+        }"#,
+    )
+    .unwrap();
+    let lattice = scenario.lattice().unwrap();
+    assert!(scenario.records(&lattice).is_err());
+}
+
+#[test]
+fn scenario_rejects_misspelled_fields() {
+    let misspelled = [
+        r#"{ "binding": [{ "upper": { "0": 1 }, "into_ts": "B" }], "records": [] }"#,
+        r#"{ "bindings": [{ "upper": { "0": 1 }, "into": "B" }], "records": [] }"#,
+        r#"{ "records": [{ "data": "a", "from_ts": 0, "diff": 1, "fronteir": ["C"] }] }"#,
+    ];
+    for scenario in misspelled {
+        assert!(Scenario::parse(scenario).is_err(), "{scenario}");
+    }
+}
+
+fn updates() -> Vec<(String, Offset, i64)> {
+    vec![
+        ("a".to_owned(), (0, 0), 1),
+        ("b".to_owned(), (0, 1), 2),
+        ("c".to_owned(), (1, 0), 1),
+        ("d".to_owned(), (1, 1), -1),
+        ("e".to_owned(), (0, 2), 1),
+        ("f".to_owned(), (0, 3), 1),
+    ]
+}
+
+/// Reclocks the updates with every binding inserted `multiplicity` times.
+fn reclock_offsets(multiplicity: i64) -> Vec<(String, Time, i64)> {
+    let guards = timely::execute(timely::Config::process(2), move |worker| {
+        let mut probe = Handle::new();
+        let output = Rc::new(RefCell::new(Vec::new()));
+
+        let (mut source, mut remap) = worker.dataflow::<Time, _, _>(|scope| {
+            let (source_input, source) = scope.new_collection::<(String, Offset), i64>();
+            let (remap_input, remap) = scope.new_collection();
+            let output = Rc::clone(&output);
+            source
+                .reclock_offsets(&remap)
+                .inner
+                .inspect(move |(data, time, diff)| {
+                    output.borrow_mut().push((data.clone(), *time, *diff))
+                })
+                .probe_with(&mut probe);
+            (source_input, remap_input)
+        });
+
+        if worker.index() == 0 {
+            for (data, offset, diff) in updates() {
+                source.update((data, offset), diff);
+            }
+            for binding in bindings() {
+                remap.update(binding, multiplicity);
+            }
+        }
+        source.close();
+        remap.close();
+        while !probe.done() {
+            worker.step();
+        }
+        output.take()
+    })
+    .unwrap();
+    guards.join().into_iter().flat_map(Result::unwrap).collect()
+}
+
+#[test]
+fn reclock_offsets_matches_the_bindings() {
+    let output = reclock_offsets(1);
+
+    let bindings = bindings();
+    let records: Vec<ReclockedRecord<String, Offset, i64, Time>> = updates()
+        .into_iter()
+        .map(|(data, offset, diff)| {
+            let frontier: Antichain<Time> = offsets::frontier(&bindings, &offset);
+            ((data, offset, diff), frontier)
+        })
+        .collect();
+    if let Err(report) = oracle::verify(&records, &output, &Time::ELEMENTS) {
+        panic!("{report}");
+    }
+}
+
+#[test]
+fn binding_multiplicities_do_not_scale_the_output() {
+    let consolidated = |output: Vec<(String, Time, i64)>| {
+        let mut output: Vec<_> = output
+            .into_iter()
+            .map(|(data, time, diff)| ((data, time), diff))
+            .collect();
+        consolidate(&mut output);
+        output
+    };
+    assert_eq!(
+        consolidated(reclock_offsets(3)),
+        consolidated(reclock_offsets(1))
+    );
+}
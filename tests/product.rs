@@ -0,0 +1,78 @@
+use timely::order::Product;
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::harness::{self, Pipeline};
+use demo_reclock_reduce::oracle;
+use demo_reclock_reduce::product::{self, Pair};
+use demo_reclock_reduce::scenario::{Resolved, Scenario};
+
+fn pairs(pairs: &[(u64, u64)]) -> Antichain<Pair> {
+    pairs.iter().map(|&(o, i)| Product::new(o, i)).collect()
+}
+
+#[test]
+fn staircase_is_not_double_counted_at_its_joins() {
+    let records = vec![(
+        ("data".to_owned(), 0u64, 1),
+        pairs(&[(1, 3), (2, 2), (3, 1)]),
+    )];
+    let updates = harness::reclock(1, records.clone()).unwrap();
+    let data = |diff| vec![(("data".to_owned(), 0), diff)];
+    assert_eq!(
+        updates,
+        [
+            (Product::new(1, 3), data(1)),
+            (Product::new(2, 2), data(1)),
+            (Product::new(2, 3), data(-1)),
+            (Product::new(3, 1), data(1)),
+            (Product::new(3, 2), data(-1)),
+        ]
+    );
+
+    let output: Vec<_> = updates
+        .into_iter()
+        .flat_map(|(time, updates)| {
+            updates
+                .into_iter()
+                .map(move |((data, _), diff)| (data, time, diff))
+        })
+        .collect();
+    let elements = product::grid(records[0].1.iter());
+    assert_eq!(elements.len(), 16);
+    if let Err(report) = oracle::verify(&records, &output, &elements) {
+        panic!("{report}");
+    }
+}
+
+#[test]
+fn product_scenario_is_reclocked_without_double_counting() {
+    let scenario = Scenario::load("scenarios/product.json".as_ref()).unwrap();
+    let Resolved::Product { elements, records } = scenario.resolve().unwrap() else {
+        panic!("scenario is not of product times");
+    };
+    for pipeline in [Pipeline::Reduce, Pipeline::Direct] {
+        for workers in [1, 2] {
+            let batches = vec![(Product::new(0, 0), records.clone())];
+            let output: Vec<_> = harness::reclock_with(pipeline, workers, batches)
+                .unwrap()
+                .into_iter()
+                .flat_map(|(time, updates)| {
+                    updates
+                        .into_iter()
+                        .map(move |((data, _), diff)| (data, time, diff))
+                })
+                .collect();
+            if let Err(report) = oracle::verify(&records, &output, &elements) {
+                panic!("{pipeline:?} with {workers} workers: {report}");
+            }
+        }
+    }
+}
+
+#[test]
+fn parses_pairs() {
+    assert_eq!(product::parse("(1, 3)"), Ok(Product::new(1, 3)));
+    assert_eq!(product::parse(" 2,2 "), Ok(Product::new(2, 2)));
+    assert!(product::parse("(1, 3").is_err());
+    assert!(product::parse("B").is_err());
+}
@@ -0,0 +1,116 @@
+//! Property based tests of reclocking over random lattices, frontiers and diffs.
+
+use std::collections::BTreeSet;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+use timely::progress::{Antichain, Timestamp};
+
+use demo_reclock_reduce::finite::{FiniteLattice, FiniteTime};
+use demo_reclock_reduce::harness::{self, Pipeline};
+use demo_reclock_reduce::oracle::{self, ReclockedRecord};
+
+/// Builds the lattice of the smallest family of subsets of `0..4` that contains `sets` and the
+/// full set, and is closed under intersection.
+///
+/// Every finite lattice is such a family over a large enough universe, but with a universe of four
+/// elements only the lattices of at most sixteen elements that embed into the subsets of `0..4`
+/// are generated. These include chains, and the non distributive M3 and N5.
+fn closure_lattice(sets: &[u8]) -> FiniteLattice {
+    let mut family: BTreeSet<u8> = sets.iter().copied().collect();
+    family.insert(0b1111);
+    loop {
+        let intersections: Vec<u8> = family
+            .iter()
+            .flat_map(|a| family.iter().map(move |b| a & b))
+            .filter(|set| !family.contains(set))
+            .collect();
+        if intersections.is_empty() {
+            break;
+        }
+        family.extend(intersections);
+    }
+
+    let subset = |a: u8, b: u8| a != b && a & b == a;
+    let name = |set: u8| format!("{set:04b}");
+    let names: Vec<String> = family.iter().map(|&set| name(set)).collect();
+    let covers: Vec<(String, String)> = family
+        .iter()
+        .flat_map(|&a| family.iter().map(move |&b| (a, b)))
+        .filter(|&(a, b)| subset(a, b) && !family.iter().any(|&c| subset(a, c) && subset(c, b)))
+        .map(|(a, b)| (name(a), name(b)))
+        .collect();
+
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let covers: Vec<(&str, &str)> = covers
+        .iter()
+        .map(|(a, b)| (a.as_str(), b.as_str()))
+        .collect();
+    FiniteLattice::from_hasse(&names, &covers).unwrap()
+}
+
+/// A record with a random non zero diff and a frontier drawn from the elements of the lattice.
+fn record() -> impl Strategy<Value = (u8, i64, Vec<Index>)> {
+    let diff = prop_oneof![-3i64..=-1, 1i64..=3];
+    (0u8..3, diff, prop::collection::vec(any::<Index>(), 0..4))
+}
+
+/// Resolves the frontiers of generated records against the elements of a lattice.
+fn resolve(
+    records: Vec<(u8, i64, Vec<Index>)>,
+    elements: &[FiniteTime],
+) -> Vec<ReclockedRecord<u8, u64, i64, FiniteTime>> {
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(from_ts, (data, diff, frontier))| {
+            let frontier: Antichain<FiniteTime> = frontier
+                .iter()
+                .map(|index| index.get(elements).clone())
+                .collect();
+            ((data, from_ts as u64, diff), frontier)
+        })
+        .collect()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn reclocked_records_are_never_double_counted(
+        sets in prop::collection::vec(0u8..16, 0..6),
+        records in prop::collection::vec(record(), 1..6),
+        workers in 1usize..=3,
+    ) {
+        let lattice = closure_lattice(&sets);
+        let elements = lattice.elements();
+        let records = resolve(records, &elements);
+
+        let output: Vec<_> = harness::reclock(workers, records.clone())
+            .unwrap()
+            .into_iter()
+            .flat_map(|(time, updates)| {
+                updates
+                    .into_iter()
+                    .map(move |((data, _from_ts), diff)| (data, time.clone(), diff))
+            })
+            .collect();
+        if let Err(report) = oracle::verify(&records, &output, &elements) {
+            panic!("{report}");
+        }
+    }
+
+    #[test]
+    fn direct_pipeline_matches_reduce_pipeline(
+        sets in prop::collection::vec(0u8..16, 0..6),
+        records in prop::collection::vec(record(), 1..6),
+    ) {
+        let lattice = closure_lattice(&sets);
+        let records = resolve(records, &lattice.elements());
+        let batches = vec![(FiniteTime::minimum(), records)];
+
+        let reduce = harness::reclock_with(Pipeline::Reduce, 1, batches.clone()).unwrap();
+        let direct = harness::reclock_with(Pipeline::Direct, 1, batches).unwrap();
+        prop_assert_eq!(direct, reduce);
+    }
+}
@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::PathBuf;
+
+use demo_reclock_reduce::finite::FiniteTime;
+use demo_reclock_reduce::harness::Updates;
+use demo_reclock_reduce::ingest::Ingest;
+use demo_reclock_reduce::offsets::Offset;
+use demo_reclock_reduce::remap::RemapLog;
+use demo_reclock_reduce::scenario::{Binding, Scenario};
+
+/// A path for a log that no other test uses, without a log at it.
+fn log_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("remap-{}-{name}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn binding(upper: &[(u32, u64)], into_ts: &str) -> Binding {
+    Binding {
+        upper: upper.iter().copied().collect(),
+        into_ts: into_ts.to_owned(),
+    }
+}
+
+/// Ingests `lines` in the lattice of `Time`, using the log at `path` if one is given, and returns
+/// all the updates that were released.
+fn ingest(
+    lines: &'static [&'static str],
+    path: Option<PathBuf>,
+) -> Updates<String, Offset, FiniteTime, i64> {
+    let lattice = Scenario::parse(r#"{ "records": [] }"#)
+        .unwrap()
+        .lattice()
+        .unwrap();
+    timely::execute_directly(move |worker| {
+        let mut ingest = Ingest::new(worker, move |name: &str| {
+            lattice.element(name).ok_or_else(|| name.to_owned())
+        });
+        if let Some(path) = path {
+            ingest = ingest
+                .with_remap_log(RemapLog::open(&path).unwrap())
+                .unwrap();
+        }
+        let mut released = vec![];
+        for line in lines {
+            released.extend(ingest.line(worker, line).unwrap());
+        }
+        released.extend(ingest.finish(worker));
+        released
+    })
+}
+
+#[test]
+fn bindings_are_read_back() {
+    let path = log_path("read-back");
+    let mut log = RemapLog::open(&path).unwrap();
+    log.append(binding(&[(0, 1)], "B")).unwrap();
+    log.append(binding(&[(0, 3), (1, 2)], "E")).unwrap();
+    log.append(binding(&[(0, 1)], "B")).unwrap();
+    drop(log);
+
+    let log = RemapLog::open(&path).unwrap();
+    assert_eq!(
+        log.bindings(),
+        [binding(&[(0, 1)], "B"), binding(&[(0, 3), (1, 2)], "E")]
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn interrupted_appends_are_discarded() {
+    let path = log_path("interrupted");
+    let mut log = RemapLog::open(&path).unwrap();
+    log.append(binding(&[(0, 1)], "B")).unwrap();
+    drop(log);
+    let mut contents = fs::read_to_string(&path).unwrap();
+    contents.push_str(r#"{ "upper": { "0": "#);
+    fs::write(&path, contents).unwrap();
+
+    let mut log = RemapLog::open(&path).unwrap();
+    assert_eq!(log.bindings(), [binding(&[(0, 1)], "B")]);
+    log.append(binding(&[(0, 2)], "C")).unwrap();
+    drop(log);
+
+    let log = RemapLog::open(&path).unwrap();
+    assert_eq!(
+        log.bindings(),
+        [binding(&[(0, 1)], "B"), binding(&[(0, 2)], "C")]
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn records_keep_their_times_across_restarts() {
+    const BEFORE: &[&str] = &[
+        r#"{ "advance": ["B"] }"#,
+        r#"{ "data": "a", "from_ts": 0, "diff": 1 }"#,
+        r#"{ "advance": ["E"] }"#,
+    ];
+    // After the restart the source catches up before advancing its frontier
+    const AFTER: &[&str] = &[
+        r#"{ "data": "a", "from_ts": 0, "diff": 1 }"#,
+        r#"{ "data": "b", "from_ts": 1, "diff": 1 }"#,
+        r#"{ "advance": ["E"] }"#,
+    ];
+    let path = log_path("restart");
+
+    let times_of = |updates: Updates<String, Offset, FiniteTime, i64>, data: &str| {
+        updates
+            .into_iter()
+            .filter(|(_, updates)| updates.iter().any(|((d, _), _)| d == data))
+            .map(|(time, _)| format!("{time:?}"))
+            .collect::<Vec<_>>()
+    };
+
+    let before = ingest(BEFORE, Some(path.clone()));
+    assert_eq!(times_of(before, "a"), ["B"]);
+    assert_eq!(
+        RemapLog::open(&path).unwrap().bindings(),
+        [binding(&[(0, 1)], "B")]
+    );
+
+    let after = ingest(AFTER, Some(path.clone()));
+    assert_eq!(times_of(after.clone(), "a"), ["B"]);
+    assert_eq!(times_of(after, "b"), ["A"]);
+
+    // Without the log the record is reclocked into the frontier it arrives at
+    let forgotten = ingest(AFTER, None);
+    assert_eq!(times_of(forgotten, "a"), ["A"]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn records_replayed_after_the_frontier_keep_their_times() {
+    const BEFORE: &[&str] = &[
+        r#"{ "advance": ["B"] }"#,
+        r#"{ "data": "a", "from_ts": 0, "diff": 1 }"#,
+        r#"{ "advance": ["E"] }"#,
+    ];
+    // After the restart the frontier advances past the binding of the record before it is replayed
+    const AFTER: &[&str] = &[
+        r#"{ "advance": ["B"] }"#,
+        r#"{ "advance": ["E"] }"#,
+        r#"{ "data": "a", "from_ts": 0, "diff": 1 }"#,
+        r#"{ "data": "b", "from_ts": 1, "diff": 1 }"#,
+        r#"{ "advance": ["G"] }"#,
+    ];
+    let path = log_path("replay-after-advance");
+
+    ingest(BEFORE, Some(path.clone()));
+    let after: Vec<_> = ingest(AFTER, Some(path.clone()))
+        .into_iter()
+        .map(|(time, updates)| (format!("{time:?}"), updates))
+        .collect();
+    assert_eq!(
+        after,
+        [
+            ("B".to_owned(), vec![(("a".to_owned(), (0, 0)), 1)]),
+            ("E".to_owned(), vec![(("b".to_owned(), (0, 1)), 1)]),
+        ]
+    );
+    assert_eq!(
+        RemapLog::open(&path).unwrap().bindings(),
+        [binding(&[(0, 1)], "B"), binding(&[(0, 2)], "E")]
+    );
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use demo_reclock_reduce::order::Time;
+
+use common::{check, reclock, record};
+
+#[test]
+fn negative_diffs() {
+    check(vec![(
+        Time::A,
+        vec![
+            record("data", 0, -2, &[Time::B, Time::C, Time::D]),
+            record("other", 1, -1, &[Time::B, Time::D]),
+        ],
+    )]);
+}
+
+#[test]
+fn retraction_at_the_same_frontier() {
+    let frontier = [Time::B, Time::C, Time::D];
+    check(vec![
+        (Time::A, vec![record("data", 0, 2, &frontier)]),
+        (Time::C, vec![record("data", 0, -2, &frontier)]),
+    ]);
+    let output = reclock(vec![(
+        Time::A,
+        vec![
+            record("data", 0, 2, &frontier),
+            record("data", 0, -2, &frontier),
+        ],
+    )]);
+    assert!(output.is_empty(), "{output:?}");
+}
+
+#[test]
+fn update_into_a_later_frontier() {
+    check(vec![
+        (Time::A, vec![record("data", 0, 2, &[Time::B, Time::D])]),
+        (
+            Time::C,
+            vec![
+                record("data", 0, -2, &[Time::B, Time::D]),
+                record("data", 0, 3, &[Time::E, Time::F]),
+            ],
+        ),
+        (Time::E, vec![record("data", 5, -1, &[Time::G])]),
+    ]);
+}
+
+#[test]
+fn updates_beside_or_beyond_their_frontier() {
+    check(vec![
+        (Time::B, vec![record("beside", 1, 1, &[Time::C])]),
+        (
+            Time::C,
+            vec![
+                record("beyond", 0, 1, &[Time::A]),
+                record("between", 2, 2, &[Time::B, Time::D]),
+            ],
+        ),
+        (
+            Time::E,
+            vec![
+                record("beyond", 0, -1, &[Time::A]),
+                record("beside", 1, -1, &[Time::C]),
+            ],
+        ),
+    ]);
+}
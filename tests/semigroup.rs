@@ -0,0 +1,164 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use differential_dataflow::difference::Semigroup;
+use differential_dataflow::input::Input;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::{Inspect, Probe};
+use timely::order::PartialOrder;
+
+use demo_reclock_reduce::order::Time;
+use demo_reclock_reduce::{reclock_idempotent, Idempotent};
+
+/// Records that a record is present, however many times it is added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Present;
+
+impl Semigroup for Present {
+    fn plus_equals(&mut self, _rhs: &Self) {}
+
+    fn is_zero(&self) -> bool {
+        false
+    }
+}
+
+impl Idempotent for Present {}
+
+/// Keeps the largest value that was added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Max(u64);
+
+impl Semigroup for Max {
+    fn plus_equals(&mut self, rhs: &Self) {
+        self.0 = self.0.max(rhs.0);
+    }
+
+    fn is_zero(&self) -> bool {
+        false
+    }
+}
+
+impl Idempotent for Max {}
+
+/// Counts the updates, and is wrongly claimed to be idempotent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+struct Count(u64);
+
+impl Semigroup for Count {
+    fn plus_equals(&mut self, rhs: &Self) {
+        self.0 += rhs.0;
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Idempotent for Count {}
+
+type Update<R> = (((&'static str, u64), Vec<Time>), R);
+
+/// Reclocks the updates and returns the reclocked output.
+fn reclock<R: Idempotent + Send + Sync>(updates: Vec<Update<R>>) -> Vec<(&'static str, Time, R)> {
+    timely::execute_directly(move |worker| {
+        let mut probe = Handle::new();
+        let output = Rc::new(RefCell::new(Vec::new()));
+
+        let mut input = worker.dataflow::<Time, _, _>(|scope| {
+            let (input, source) = scope.new_collection();
+            let output = Rc::clone(&output);
+            reclock_idempotent(&source)
+                .inner
+                .inspect(move |((data, _from_ts), time, diff)| {
+                    output.borrow_mut().push((*data, *time, diff.clone()))
+                })
+                .probe_with(&mut probe);
+            input
+        });
+        for (update, diff) in updates {
+            input.update(update, diff);
+        }
+        input.close();
+        while !probe.done() {
+            worker.step();
+        }
+        output.take()
+    })
+}
+
+/// Accumulates the diff of `data` at `time`, which is absent if there are no updates for it.
+fn accumulate_at<R: Semigroup>(
+    output: &[(&'static str, Time, R)],
+    data: &str,
+    time: &Time,
+) -> Option<R> {
+    output
+        .iter()
+        .filter(|(d, t, _)| *d == data && t.less_equal(time))
+        .map(|(_, _, diff)| diff.clone())
+        .reduce(|mut accum, diff| {
+            accum.plus_equals(&diff);
+            accum
+        })
+}
+
+fn beyond(frontier: &[Time], time: &Time) -> bool {
+    frontier.iter().any(|t| t.less_equal(time))
+}
+
+#[test]
+fn presence_is_reclocked_without_corrections() {
+    let frontiers: [(&str, &[Time]); 3] = [
+        ("a", &[Time::B, Time::C, Time::D]),
+        ("b", &[Time::B, Time::D]),
+        ("c", &[Time::E, Time::F]),
+    ];
+    let updates = frontiers
+        .iter()
+        .map(|(data, frontier)| (((*data, 0), frontier.to_vec()), Present))
+        .collect();
+    let output = reclock(updates);
+
+    for (data, frontier) in frontiers {
+        for time in Time::ELEMENTS {
+            let expected = beyond(frontier, &time).then_some(Present);
+            assert_eq!(
+                accumulate_at(&output, data, &time),
+                expected,
+                "{data} at {time:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn maximum_is_reclocked_without_corrections() {
+    let updates = vec![
+        ((("a", 0), vec![Time::B, Time::C, Time::D]), Max(3)),
+        ((("a", 1), vec![Time::E]), Max(5)),
+        ((("a", 2), vec![Time::C, Time::D]), Max(1)),
+    ];
+    let output = reclock(updates);
+
+    let expected = |time: &Time| match time {
+        Time::A => None,
+        Time::E | Time::G => Some(Max(5)),
+        _ => Some(Max(3)),
+    };
+    for time in Time::ELEMENTS {
+        assert_eq!(
+            accumulate_at(&output, "a", &time),
+            expected(&time),
+            "at {time:?}"
+        );
+    }
+}
+
+// Idempotence is only checked with debug assertions enabled
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "is not idempotent")]
+fn counts_are_rejected() {
+    reclock(vec![((("a", 0), vec![Time::B, Time::C]), Count(1))]);
+}
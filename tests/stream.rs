@@ -0,0 +1,107 @@
+use timely::progress::Antichain;
+
+use demo_reclock_reduce::harness::Pipeline;
+use demo_reclock_reduce::oracle::ReclockedRecord;
+use demo_reclock_reduce::order::Time;
+use demo_reclock_reduce::stream::Reclocker;
+
+fn record(
+    data: &str,
+    from_ts: u64,
+    diff: i64,
+    frontier: &[Time],
+) -> ReclockedRecord<String, u64, i64, Time> {
+    let frontier = Antichain::from_iter(frontier.iter().copied());
+    ((data.to_owned(), from_ts, diff), frontier)
+}
+
+fn updates(data: &str, from_ts: u64, diff: i64) -> Vec<((String, u64), i64)> {
+    vec![((data.to_owned(), from_ts), diff)]
+}
+
+#[test]
+fn output_is_released_as_the_source_frontier_advances() {
+    for pipeline in [Pipeline::Reduce, Pipeline::Direct] {
+        timely::execute_directly(move |worker| {
+            let mut reclocker = Reclocker::new(worker, pipeline);
+
+            reclocker
+                .insert(record("a", 0, 1, &[Time::B, Time::C]))
+                .unwrap();
+            reclocker
+                .advance(&Antichain::from_iter([Time::B, Time::C, Time::D]))
+                .unwrap();
+            assert!(reclocker.step(worker).is_empty());
+
+            reclocker.insert(record("b", 1, 1, &[Time::D])).unwrap();
+            reclocker
+                .advance(&Antichain::from_iter([Time::E, Time::F]))
+                .unwrap();
+            assert_eq!(
+                reclocker.step(worker),
+                [
+                    (Time::B, updates("a", 0, 1)),
+                    (Time::C, updates("a", 0, 1)),
+                    (Time::D, updates("b", 1, 1)),
+                ]
+            );
+
+            reclocker.close();
+            assert_eq!(reclocker.step(worker), [(Time::E, updates("a", 0, -1))]);
+            assert!(reclocker.done());
+        });
+    }
+}
+
+#[test]
+fn records_are_accepted_into_frontiers_whose_meet_is_closed() {
+    for pipeline in [Pipeline::Reduce, Pipeline::Direct] {
+        timely::execute_directly(move |worker| {
+            let mut reclocker = Reclocker::new(worker, pipeline);
+            reclocker
+                .advance(&Antichain::from_iter([Time::B, Time::C, Time::D]))
+                .unwrap();
+
+            // The meet of B and D is A, which the source has moved past
+            reclocker
+                .insert(record("a", 0, 1, &[Time::B, Time::D]))
+                .unwrap();
+            reclocker
+                .advance(&Antichain::from_iter([Time::E, Time::F]))
+                .unwrap();
+            assert_eq!(
+                reclocker.step(worker),
+                [(Time::B, updates("a", 0, 1)), (Time::D, updates("a", 0, 1)),]
+            );
+
+            reclocker.close();
+            assert_eq!(reclocker.step(worker), [(Time::G, updates("a", 0, -1))]);
+            assert!(reclocker.done());
+        });
+    }
+}
+
+#[test]
+fn closed_times_are_rejected() {
+    timely::execute_directly(|worker| {
+        let mut reclocker = Reclocker::<String, u64, Time, i64>::new(worker, Pipeline::Reduce);
+        reclocker
+            .advance(&Antichain::from_iter([Time::E, Time::F]))
+            .unwrap();
+
+        // B and C are not beyond the source frontier, although their join is
+        assert!(reclocker
+            .insert(record("a", 0, 1, &[Time::B, Time::C]))
+            .is_err());
+        assert!(reclocker.advance(&Antichain::from_iter([Time::C])).is_err());
+        assert_eq!(
+            reclocker.frontier(),
+            Antichain::from_iter([Time::E, Time::F])
+        );
+
+        reclocker.insert(record("a", 0, 1, &[Time::G])).unwrap();
+        reclocker.close();
+        assert!(reclocker.insert(record("b", 1, 1, &[Time::G])).is_err());
+        assert_eq!(reclocker.step(worker), [(Time::G, updates("a", 0, 1))]);
+    });
+}
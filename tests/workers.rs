@@ -0,0 +1,109 @@
+//! Runs the binary with different worker and process configurations and checks that they all
+//! produce the same output.
+
+use std::fs;
+use std::net::TcpListener;
+use std::process::{Command, Output, Stdio};
+
+const BIN: &str = env!("CARGO_BIN_EXE_demo-reclock-reduce");
+const SCENARIOS: [&str; 5] = [
+    "scenarios/demo.json",
+    "scenarios/diamond.json",
+    "scenarios/offsets.json",
+    "scenarios/product.json",
+    "scenarios/retractions.json",
+];
+
+fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs a scenario in a single process and returns its output.
+fn run(scenario: &str, format: &str, workers: usize) -> String {
+    let output = Command::new(BIN)
+        .args(["run", scenario, "--format", format])
+        .args(["-w", &workers.to_string()])
+        .output()
+        .unwrap();
+    stdout(output)
+}
+
+/// Runs a scenario in two processes on localhost and returns the output of the first one.
+fn run_cluster(scenario: &str, format: &str) -> String {
+    // Reserve two ports for the processes, which is racy but good enough for a test
+    let listeners = [
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+        TcpListener::bind("127.0.0.1:0").unwrap(),
+    ];
+    let hosts: Vec<_> = listeners
+        .iter()
+        .map(|listener| listener.local_addr().unwrap().to_string())
+        .collect();
+    drop(listeners);
+    let hostfile = std::env::temp_dir().join(format!(
+        "demo-reclock-reduce-hosts-{}-{}-{format}",
+        std::process::id(),
+        scenario.replace('/', "-")
+    ));
+    fs::write(&hostfile, hosts.join("\n")).unwrap();
+
+    // Both processes must be running before either of them can make progress
+    let processes: Vec<_> = (0..2)
+        .map(|process| {
+            Command::new(BIN)
+                .args(["run", scenario, "--format", format])
+                .args(["-n", "2", "-p", &process.to_string()])
+                .arg("-h")
+                .arg(&hostfile)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    let outputs: Vec<_> = processes
+        .into_iter()
+        .map(|process| process.wait_with_output())
+        .collect();
+    let _ = fs::remove_file(&hostfile);
+    let mut outputs = outputs.into_iter().map(|output| stdout(output.unwrap()));
+    let first = outputs.next().unwrap();
+    assert_eq!(outputs.next().unwrap(), "");
+    first
+}
+
+#[test]
+fn output_does_not_depend_on_the_number_of_workers() {
+    for scenario in SCENARIOS {
+        for format in ["text", "json"] {
+            let expected = run(scenario, format, 1);
+            assert!(!expected.is_empty());
+            for workers in [2, 4] {
+                assert_eq!(
+                    run(scenario, format, workers),
+                    expected,
+                    "{scenario} with {workers} workers"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn output_does_not_depend_on_the_number_of_processes() {
+    // Timestamps only cross process boundaries in serialized form, which the JSON output prints
+    for scenario in SCENARIOS {
+        for format in ["text", "json"] {
+            assert_eq!(
+                run_cluster(scenario, format),
+                run(scenario, format, 1),
+                "{scenario} in {format}"
+            );
+        }
+    }
+}